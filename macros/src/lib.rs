@@ -0,0 +1,336 @@
+// grammars checked at compile time: a grammar error becomes a compile error pointing at the faulty
+// rule, and a valid grammar expands to the code building the `Grammar`, with nothing left to parse at
+// runtime. A `Grammar` holds `HashMap`s, which can't be built in a const context, so the macros can also
+// declare a static themselves, as a `LazyLock<Grammar>` building the grammar on first use
+use backus_naur_parser::bnf::element::Element;
+use backus_naur_parser::bnf::grammar::{parse_grammar_checked, Grammar};
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+use std::fmt::Write;
+
+// bnf! { <expr> ::= <term> "+" <expr> | <term> ... }, one rule per line like in a .bnf file, or
+// bnf! { static NAME = <expr> ::= ... } to declare a static grammar
+#[proc_macro]
+pub fn bnf(input: TokenStream) -> TokenStream {
+    let (declaration, input) = match split_declaration(input) {
+        Ok(split) => split,
+        Err(error) => return error,
+    };
+    let mut source = Source {
+        text: String::new(),
+        length: 0,
+        tokens: Vec::new(),
+        line: 0,
+        column: 0,
+    };
+    source.push_stream(input);
+
+    let chars: Vec<char> = source.text.chars().collect();
+    match parse_grammar_checked(&chars, 0) {
+        Ok(grammar) => expand(&grammar, None, declaration.as_deref()),
+        Err(error) => compile_error(source.span_at(error.index()), &error.to_string()),
+    }
+}
+
+// bnf_file!("grammar.bnf"), the path being relative to the directory of Cargo.toml, or
+// bnf_file!(static NAME = "grammar.bnf") to declare a static grammar
+#[proc_macro]
+pub fn bnf_file(input: TokenStream) -> TokenStream {
+    let (declaration, input) = match split_declaration(input) {
+        Ok(split) => split,
+        Err(error) => return error,
+    };
+    let mut tokens = input.into_iter();
+    let (Some(TokenTree::Literal(literal)), None) = (tokens.next(), tokens.next()) else {
+        return compile_error(
+            Span::call_site(),
+            "expected the path of a .bnf file as a string literal",
+        );
+    };
+    let span = literal.span();
+    let Some(relative_path) = string_value(&literal.to_string()) else {
+        return compile_error(span, "expected the path of a .bnf file as a string literal");
+    };
+
+    let directory = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let path = std::path::Path::new(&directory).join(&relative_path);
+    let source = match std::fs::read_to_string(&path) {
+        Ok(source) => source,
+        Err(error) => {
+            return compile_error(span, &format!("couldn't read {}: {error}", path.display()))
+        }
+    };
+
+    let chars: Vec<char> = source.chars().collect();
+    match parse_grammar_checked(&chars, 0) {
+        Ok(grammar) => expand(
+            &grammar,
+            Some(&path.to_string_lossy()),
+            declaration.as_deref(),
+        ),
+        Err(error) => {
+            // the span can't point inside the file, the message tells where instead
+            let (line, column) = error.position(&chars);
+            compile_error(span, &format!("{relative_path}:{line}:{column}: {error}"))
+        }
+    }
+}
+
+// splits `pub static NAME = ...` into the declaration without the `=` and what follows it, the input
+// being left as is when it doesn't start with a declaration
+fn split_declaration(input: TokenStream) -> Result<(Option<String>, TokenStream), TokenStream> {
+    let tokens: Vec<TokenTree> = input.into_iter().collect();
+    let is_ident = |token: Option<&TokenTree>, name: &str| matches!(token, Some(TokenTree::Ident(ident)) if ident.to_string() == name);
+    let mut index = 0;
+    if is_ident(tokens.first(), "pub") {
+        index += 1;
+        // pub(crate), pub(super), ...
+        if let Some(TokenTree::Group(group)) = tokens.get(index) {
+            if group.delimiter() == Delimiter::Parenthesis {
+                index += 1;
+            }
+        }
+    }
+    if !is_ident(tokens.get(index), "static") {
+        if index == 0 {
+            return Ok((None, TokenStream::from_iter(tokens)));
+        }
+        let span = tokens.get(index).map_or(Span::call_site(), TokenTree::span);
+        return Err(compile_error(
+            span,
+            "expected `static` after the visibility",
+        ));
+    }
+    let Some(TokenTree::Ident(_)) = tokens.get(index + 1) else {
+        let span = tokens
+            .get(index + 1)
+            .map_or(Span::call_site(), TokenTree::span);
+        return Err(compile_error(span, "expected the name of the static"));
+    };
+    match tokens.get(index + 2) {
+        Some(TokenTree::Punct(punct)) if punct.as_char() == '=' => {}
+        token => {
+            let span = token.map_or(Span::call_site(), TokenTree::span);
+            return Err(compile_error(
+                span,
+                "expected `=` after the name of the static, which is a `LazyLock<Grammar>`",
+            ));
+        }
+    }
+    let declaration = TokenStream::from_iter(tokens[..index + 2].iter().cloned()).to_string();
+    Ok((
+        Some(declaration),
+        TokenStream::from_iter(tokens[index + 3..].iter().cloned()),
+    ))
+}
+
+// the text of the grammar rebuilt from the tokens of the macro, keeping their layout
+struct Source {
+    text: String,
+    // the number of characters of the text
+    length: usize,
+    // where every token starts in the text, with its span
+    tokens: Vec<(usize, Span)>,
+    // where the last token ends in the code
+    line: usize,
+    column: usize,
+}
+
+impl Source {
+    fn push_stream(&mut self, stream: TokenStream) {
+        for token in stream {
+            match token {
+                TokenTree::Group(group) => {
+                    let (open, close) = match group.delimiter() {
+                        Delimiter::Parenthesis => ("(", ")"),
+                        Delimiter::Brace => ("{", "}"),
+                        Delimiter::Bracket => ("[", "]"),
+                        Delimiter::None => ("", ""),
+                    };
+                    self.push(open, group.span_open());
+                    self.push_stream(group.stream());
+                    self.push(close, group.span_close());
+                }
+                token => {
+                    let span = token.span();
+                    let text = span.source_text().unwrap_or_else(|| token.to_string());
+                    self.push(&text, span);
+                }
+            }
+        }
+    }
+
+    fn push(&mut self, text: &str, span: Span) {
+        if text.is_empty() {
+            return;
+        }
+        // the rules are separated by a single line break, the spaces within a line are kept
+        if !self.tokens.is_empty() {
+            if span.line() > self.line {
+                self.text.push('\n');
+                self.length += 1;
+            } else {
+                let spaces = span.column().saturating_sub(self.column);
+                self.text.push_str(&" ".repeat(spaces));
+                self.length += spaces;
+            }
+        }
+        self.tokens.push((self.length, span));
+        self.text.push_str(text);
+        self.length += text.chars().count();
+        self.line = span.end().line();
+        self.column = span.end().column();
+    }
+
+    fn span_at(&self, index: usize) -> Span {
+        self.tokens
+            .iter()
+            .rev()
+            .find(|(start, _)| *start <= index)
+            .map_or(Span::call_site(), |(_, span)| *span)
+    }
+}
+
+fn compile_error(span: Span, message: &str) -> TokenStream {
+    let mut message = Literal::string(message);
+    message.set_span(span);
+    let mut bang = Punct::new('!', Spacing::Alone);
+    bang.set_span(span);
+    let mut arguments = Group::new(
+        Delimiter::Parenthesis,
+        TokenStream::from(TokenTree::Literal(message)),
+    );
+    arguments.set_span(span);
+    TokenStream::from_iter([
+        TokenTree::Ident(Ident::new("compile_error", span)),
+        TokenTree::Punct(bang),
+        TokenTree::Group(arguments),
+    ])
+}
+
+// the value of a string literal, or None if the literal isn't a string
+fn string_value(literal: &str) -> Option<String> {
+    if let Some(raw) = literal.strip_prefix('r') {
+        let hashes = raw.len() - raw.trim_start_matches('#').len();
+        let quoted = &raw[hashes..raw.len() - hashes];
+        return quoted
+            .strip_prefix('"')
+            .and_then(|quoted| quoted.strip_suffix('"'))
+            .map(str::to_string);
+    }
+    let quoted = literal.strip_prefix('"')?.strip_suffix('"')?;
+    let mut value = String::new();
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => value.push('\n'),
+            't' => value.push('\t'),
+            'r' => value.push('\r'),
+            '0' => value.push('\0'),
+            c => value.push(c),
+        }
+    }
+    Some(value)
+}
+
+// the code building the grammar, with the file it comes from included so a change of it triggers a
+// rebuild, wrapped in the declaration of a static if there is one
+fn expand(grammar: &Grammar, path: Option<&str>, declaration: Option<&str>) -> TokenStream {
+    let mut code = String::from("{");
+    if let Some(path) = path {
+        write!(code, "const _: &str = ::core::include_str!({path:?});").unwrap();
+    }
+    code.push_str("let mut references = ::std::collections::HashMap::new();");
+    code.push_str("let mut maps = ::std::collections::HashMap::new();");
+    for label_index in grammar.labels.iter() {
+        write!(
+            code,
+            "references.insert({label_index}usize, ::std::string::String::from({:?}));",
+            grammar.references[label_index]
+        )
+        .unwrap();
+        write!(code, "maps.insert({label_index}usize, ::backus_naur_parser::bnf::alternation::Alternation {{ sequences: ::std::vec![").unwrap();
+        for sequence in grammar.maps[label_index].sequences.iter() {
+            code.push_str("::backus_naur_parser::bnf::sequence::Sequence { elements: ::std::vec![");
+            for element in sequence.elements.iter() {
+                code.push_str("::backus_naur_parser::bnf::element::Element::");
+                match element {
+                    Element::Empty => code.push_str("Empty"),
+                    Element::Range { start, end } => {
+                        write!(code, "Range {{ start: {start:?}, end: {end:?} }}").unwrap()
+                    }
+                    Element::Literal(literal) => {
+                        write!(code, "Literal(::std::string::String::from({literal:?}))").unwrap()
+                    }
+                    Element::Reference(target) => write!(code, "Reference({target}usize)").unwrap(),
+                }
+                code.push(',');
+            }
+            code.push_str("] },");
+        }
+        code.push_str("] });");
+    }
+    let labels: Vec<String> = grammar
+        .labels
+        .iter()
+        .map(|label_index| format!("{label_index}usize"))
+        .collect();
+    write!(
+        code,
+        "::backus_naur_parser::bnf::grammar::Grammar {{ references, labels: ::std::vec![{}], maps }} }}",
+        labels.join(", ")
+    )
+    .unwrap();
+    if let Some(declaration) = declaration {
+        code = format!(
+            "{declaration}: ::std::sync::LazyLock<::backus_naur_parser::bnf::grammar::Grammar> = ::std::sync::LazyLock::new(|| {code});"
+        );
+    }
+    code.parse().expect("The expansion should be valid Rust.")
+}
+
+// the grammar errors turned into compile errors, every block but the first failing to compile
+#[cfg(doctest)]
+/// ```
+/// let _ = backus_naur_parser_macros::bnf! { <a> ::= "a" <b>
+///     <b> ::= "b" };
+/// backus_naur_parser_macros::bnf! { pub(crate) static A = <a> ::= "a" }
+/// ```
+///
+/// An undefined rule:
+/// ```compile_fail
+/// let _ = backus_naur_parser_macros::bnf! { <a> ::= "a" <c>
+///     <b> ::= "b" };
+/// ```
+///
+/// A rule defined twice:
+/// ```compile_fail
+/// let _ = backus_naur_parser_macros::bnf! { <a> ::= "a" <b>
+///     <b> ::= "b"
+///     <b> ::= "c" };
+/// ```
+///
+/// A line that isn't a rule:
+/// ```compile_fail
+/// let _ = backus_naur_parser_macros::bnf! { <a> ::= "a" <b>
+///     <b> "b" };
+/// ```
+///
+/// A file that doesn't exist:
+/// ```compile_fail
+/// let _ = backus_naur_parser_macros::bnf_file!("missing.bnf");
+/// ```
+///
+/// A static declared with its type, which can't be `Grammar`:
+/// ```compile_fail
+/// backus_naur_parser_macros::bnf! { static A: Grammar = <a> ::= "a" }
+/// ```
+///
+/// Something else than a path:
+/// ```compile_fail
+/// let _ = backus_naur_parser_macros::bnf_file!(42);
+/// ```
+struct CompileErrors;
@@ -0,0 +1,58 @@
+// the grammars use ε, which isn't a typo for a latin letter
+#![allow(mixed_script_confusables)]
+
+use backus_naur_parser::bnf::grammar::{parse_grammar, Grammar};
+use backus_naur_parser_macros::{bnf, bnf_file};
+
+bnf! {
+    static NUMBERS = <number> ::= <digit> <number> | <digit>
+    <digit> ::= "0"..="9"
+}
+
+bnf_file!(pub(crate) static EQUATION = "../examples/equation.bnf");
+
+fn grammar(text: &str) -> Grammar {
+    let chars: Vec<char> = text.chars().collect();
+    parse_grammar(&chars, 0).expect("The grammar should be valid.")
+}
+
+fn assert_same(expanded: &Grammar, parsed: &Grammar) {
+    assert_eq!(expanded.references, parsed.references);
+    assert_eq!(expanded.labels, parsed.labels);
+    assert_eq!(expanded.maps, parsed.maps);
+}
+
+#[test]
+fn inline_grammar() {
+    let expanded = bnf! {
+        <greeting> ::= "hello" <space> <name> | "hi"
+        <space> ::= " " | ε
+        <name> ::= "a"..="z" <name> | "a"..="z"
+    };
+    assert_same(
+        &expanded,
+        &grammar("<greeting> ::= \"hello\" <space> <name> | \"hi\"\n<space> ::= \" \" | ε\n<name> ::= \"a\"..=\"z\" <name> | \"a\"..=\"z\"\n"),
+    );
+    assert!(expanded.parse("hello bob").is_ok());
+    assert!(expanded.parse("hello").is_err());
+}
+
+#[test]
+fn grammar_file() {
+    let expanded = bnf_file!("../examples/equation.bnf");
+    let path = format!("{}/../examples/equation.bnf", env!("CARGO_MANIFEST_DIR"));
+    let parsed = grammar(&std::fs::read_to_string(path).expect("The example should be readable."));
+    assert_same(&expanded, &parsed);
+    assert!(expanded.parse("(1 + 2) * 3").is_ok());
+}
+
+#[test]
+fn static_grammars() {
+    assert!(NUMBERS.parse("2024").is_ok());
+    assert!(NUMBERS.parse("20x4").is_err());
+    assert_same(
+        &NUMBERS,
+        &grammar("<number> ::= <digit> <number> | <digit>\n<digit> ::= \"0\"..=\"9\"\n"),
+    );
+    assert!(EQUATION.parse("(1 + 2) * 3").is_ok());
+}
@@ -0,0 +1,167 @@
+use crate::bnf::element::Element;
+use crate::bnf::grammar::{self, Grammar};
+use crate::random::Random;
+use std::collections::BTreeSet;
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    // the longest input tried
+    pub max_length: usize,
+    // past this many inputs of a given length, random inputs of that length are tried instead
+    pub max_inputs_per_length: usize,
+    pub seed: u64,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            max_length: 8,
+            max_inputs_per_length: 100_000,
+            seed: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Difference {
+    pub input: String,
+    // false when the input is accepted by the other grammar instead
+    pub accepted_by_first: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    // the shortest input found that only one grammar accepts
+    pub difference: Option<Difference>,
+    // every input up to this length was tried, longer ones were sampled
+    pub exhaustive_length: Option<usize>,
+    pub checked: usize,
+}
+
+#[derive(Debug, Clone)]
+pub enum Error {
+    // the parser gave up on this input, usually because of left recursion
+    StackOverflow { input: String },
+}
+
+impl Grammar {
+    pub fn compare(&self, other: &Grammar, options: &Options) -> Result<Comparison, Error> {
+        let alphabet = alphabet(&[self, other]);
+        let mut random = Random::new(options.seed);
+        let mut comparison = Comparison {
+            difference: None,
+            exhaustive_length: None,
+            checked: 0,
+        };
+
+        for length in 0..=options.max_length {
+            // an empty alphabet only leaves the empty input
+            if alphabet.is_empty() && length > 0 {
+                break;
+            }
+
+            let total = (alphabet.len() as u128).checked_pow(length as u32);
+            let exhaustive =
+                total.is_some_and(|total| total <= options.max_inputs_per_length as u128);
+            let count = if exhaustive {
+                total.unwrap_or(0) as usize
+            } else {
+                options.max_inputs_per_length
+            };
+
+            let mut digits = vec![0; length];
+            for _ in 0..count {
+                if !exhaustive {
+                    for digit in digits.iter_mut() {
+                        *digit = random.below(alphabet.len());
+                    }
+                }
+                let input: String = digits.iter().map(|&digit| alphabet[digit]).collect();
+                comparison.checked += 1;
+
+                let accepted_by_first = accepts(self, &input)?;
+                if accepted_by_first != accepts(other, &input)? {
+                    comparison.difference = Some(Difference {
+                        input,
+                        accepted_by_first,
+                    });
+                    return Ok(comparison);
+                }
+
+                // move to the next input in shortlex order
+                if exhaustive {
+                    for digit in digits.iter_mut().rev() {
+                        *digit += 1;
+                        if *digit < alphabet.len() {
+                            break;
+                        }
+                        *digit = 0;
+                    }
+                }
+            }
+
+            if exhaustive && comparison.exhaustive_length == length.checked_sub(1) {
+                comparison.exhaustive_length = Some(length);
+            }
+        }
+
+        Ok(comparison)
+    }
+}
+
+fn accepts(grammar: &Grammar, input: &str) -> Result<bool, Error> {
+    match grammar.parse(input) {
+        Ok(_) => Ok(true),
+        Err(grammar::Error::NoMatch) => Ok(false),
+        Err(grammar::Error::StackOverflow) => Err(Error::StackOverflow {
+            input: input.to_string(),
+        }),
+        Err(grammar::Error::Stopped) => {
+            unreachable!("Only the debugger should stop a parse.")
+        }
+    }
+}
+
+fn alphabet(grammars: &[&Grammar]) -> Vec<char> {
+    // the characters are split in intervals no terminal can tell apart,
+    // so trying a single character of each interval is as good as trying all of them
+    let mut covered: Vec<(u32, u32)> = Vec::new();
+    for grammar in grammars {
+        for alternation in grammar.maps.values() {
+            for sequence in alternation.sequences.iter() {
+                for element in sequence.elements.iter() {
+                    match element {
+                        Element::Literal(literal) => {
+                            covered.extend(literal.chars().map(|c| (c as u32, c as u32)))
+                        }
+                        Element::Range { start, end } => covered.push((*start as u32, *end as u32)),
+                        Element::Empty | Element::Reference(_) => {}
+                    }
+                }
+            }
+        }
+    }
+
+    let mut boundaries = BTreeSet::new();
+    for (start, end) in covered.iter() {
+        boundaries.insert(*start);
+        boundaries.insert(end + 1);
+    }
+    let boundaries: Vec<u32> = boundaries.into_iter().collect();
+
+    let mut alphabet = Vec::new();
+    for window in boundaries.windows(2) {
+        let (low, high) = (window[0], window[1] - 1);
+        if !covered
+            .iter()
+            .any(|(start, end)| *start <= low && high <= *end)
+        {
+            continue;
+        }
+        // skip the surrogates, which aren't valid characters
+        if let Some(c) = (low..=high).find_map(char::from_u32) {
+            alphabet.push(c);
+        }
+    }
+    alphabet
+}
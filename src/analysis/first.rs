@@ -0,0 +1,180 @@
+use crate::bnf::element::Element;
+use crate::bnf::grammar::Grammar;
+use std::collections::{HashMap, HashSet};
+
+// a set of characters, as inclusive intervals which may overlap
+pub type CharacterSet = Vec<(char, char)>;
+
+// for every rule, the characters its non empty matches can start with
+pub fn first_sets(grammar: &Grammar, nullable: &HashSet<usize>) -> HashMap<usize, CharacterSet> {
+    let mut sets: HashMap<usize, CharacterSet> = grammar
+        .labels
+        .iter()
+        .map(|label_index| (*label_index, Vec::new()))
+        .collect();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for label_index in grammar.labels.iter() {
+            for sequence in grammar.maps[label_index].sequences.iter() {
+                for interval in sequence_first(&sequence.elements, &sets, nullable) {
+                    let set = sets.get_mut(label_index).expect("Set should exist.");
+                    if !set.contains(&interval) {
+                        set.push(interval);
+                        changed = true;
+                    }
+                }
+            }
+        }
+    }
+    for set in sets.values_mut() {
+        set.sort();
+    }
+    sets
+}
+
+// the characters the elements can start with, looking past the ones matching the empty string
+pub fn sequence_first(
+    elements: &[Element],
+    first_sets: &HashMap<usize, CharacterSet>,
+    nullable: &HashSet<usize>,
+) -> CharacterSet {
+    let mut set = Vec::new();
+    for element in elements.iter() {
+        match element {
+            Element::Empty => continue,
+            Element::Literal(literal) => match literal.chars().next() {
+                Some(c) => {
+                    set.push((c, c));
+                    break;
+                }
+                None => continue,
+            },
+            Element::Range { start, end } => {
+                set.push((*start, *end));
+                break;
+            }
+            Element::Reference(target) => {
+                set.extend(first_sets[target].iter().copied());
+                if !nullable.contains(target) {
+                    break;
+                }
+            }
+        }
+    }
+    set.sort();
+    set.dedup();
+    set
+}
+
+pub fn elements_nullable(elements: &[Element], nullable: &HashSet<usize>) -> bool {
+    elements.iter().all(|element| match element {
+        Element::Empty => true,
+        Element::Literal(literal) => literal.is_empty(),
+        Element::Range { .. } => false,
+        Element::Reference(target) => nullable.contains(target),
+    })
+}
+
+// for every rule, the characters that can come right after one of its matches
+pub fn follow_sets(
+    grammar: &Grammar,
+    first_sets: &HashMap<usize, CharacterSet>,
+    nullable: &HashSet<usize>,
+) -> HashMap<usize, CharacterSet> {
+    let mut sets: HashMap<usize, CharacterSet> = grammar
+        .labels
+        .iter()
+        .map(|label_index| (*label_index, Vec::new()))
+        .collect();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for label_index in grammar.labels.iter() {
+            for sequence in grammar.maps[label_index].sequences.iter() {
+                for (position, element) in sequence.elements.iter().enumerate() {
+                    let Element::Reference(target) = element else {
+                        continue;
+                    };
+                    let rest = &sequence.elements[position + 1..];
+                    let mut follow = sequence_first(rest, first_sets, nullable);
+                    if elements_nullable(rest, nullable) {
+                        follow.extend(sets[label_index].iter().copied());
+                    }
+                    let set = sets.get_mut(target).expect("Set should exist.");
+                    for interval in follow {
+                        if !set.contains(&interval) {
+                            set.push(interval);
+                            changed = true;
+                        }
+                    }
+                }
+            }
+        }
+    }
+    for set in sets.values_mut() {
+        set.sort();
+    }
+    sets
+}
+
+pub fn overlap(first: &[(char, char)], second: &[(char, char)]) -> bool {
+    first.iter().any(|(first_start, first_end)| {
+        second.iter().any(|(second_start, second_end)| {
+            first_start <= second_end && second_start <= first_end
+        })
+    })
+}
+
+// the rules which can reach themselves without consuming any character, in order of definition
+pub fn left_recursive_rules(grammar: &Grammar) -> Vec<usize> {
+    let corners = left_corners(grammar, &grammar.nullable_labels());
+    grammar
+        .labels
+        .iter()
+        .copied()
+        .filter(|label_index| reaches(&corners, *label_index, *label_index))
+        .collect()
+}
+
+// for every rule, the rules that can start one of its sequences, skipping nullable prefixes
+pub fn left_corners(
+    grammar: &Grammar,
+    nullable: &HashSet<usize>,
+) -> HashMap<usize, HashSet<usize>> {
+    let mut corners = HashMap::new();
+    for label_index in grammar.labels.iter() {
+        let mut targets = HashSet::new();
+        for sequence in grammar.maps[label_index].sequences.iter() {
+            for element in sequence.elements.iter() {
+                match element {
+                    Element::Empty => continue,
+                    Element::Reference(target) => {
+                        targets.insert(*target);
+                        if !nullable.contains(target) {
+                            break;
+                        }
+                    }
+                    Element::Range { .. } | Element::Literal(_) => break,
+                }
+            }
+        }
+        corners.insert(*label_index, targets);
+    }
+    corners
+}
+
+// whether a chain of left corners leads from one rule to the other
+pub fn reaches(corners: &HashMap<usize, HashSet<usize>>, from: usize, to: usize) -> bool {
+    let mut visited = HashSet::new();
+    let mut stack: Vec<usize> = corners[&from].iter().copied().collect();
+    while let Some(label_index) = stack.pop() {
+        if label_index == to {
+            return true;
+        }
+        if visited.insert(label_index) {
+            stack.extend(corners[&label_index].iter().copied());
+        }
+    }
+    false
+}
@@ -0,0 +1,89 @@
+use crate::bnf::element::Element;
+use crate::bnf::grammar::Grammar;
+use std::collections::HashMap;
+
+// for every rule, the rules it references, in order of first use and without duplicates, the
+// references to undefined rules being left out
+pub fn rule_graph(grammar: &Grammar) -> HashMap<usize, Vec<usize>> {
+    let mut graph = HashMap::new();
+    for label_index in grammar.labels.iter() {
+        let mut targets = Vec::new();
+        for sequence in grammar.maps[label_index].sequences.iter() {
+            for element in sequence.elements.iter() {
+                if let Element::Reference(target) = element {
+                    if grammar.maps.contains_key(target) && !targets.contains(target) {
+                        targets.push(*target);
+                    }
+                }
+            }
+        }
+        graph.insert(*label_index, targets);
+    }
+    graph
+}
+
+// groups of rules that can all reach each other (Tarjan's algorithm),
+// a group is always listed before the groups that reference it
+pub fn strongly_connected_components(grammar: &Grammar) -> Vec<Vec<usize>> {
+    let graph = rule_graph(grammar);
+    let mut state = Tarjan {
+        graph: &graph,
+        index: HashMap::new(),
+        low_link: HashMap::new(),
+        stack: Vec::new(),
+        components: Vec::new(),
+    };
+    for label_index in grammar.labels.iter() {
+        if !state.index.contains_key(label_index) {
+            state.visit(*label_index);
+        }
+    }
+    state.components
+}
+
+// a rule is recursive if it belongs to a group of several rules, or references itself
+pub fn is_recursive(graph: &HashMap<usize, Vec<usize>>, component: &[usize]) -> bool {
+    component.len() > 1 || graph[&component[0]].contains(&component[0])
+}
+
+struct Tarjan<'a> {
+    graph: &'a HashMap<usize, Vec<usize>>,
+    index: HashMap<usize, usize>,
+    low_link: HashMap<usize, usize>,
+    stack: Vec<usize>,
+    components: Vec<Vec<usize>>,
+}
+
+impl Tarjan<'_> {
+    fn visit(&mut self, label_index: usize) {
+        let index = self.index.len();
+        self.index.insert(label_index, index);
+        self.low_link.insert(label_index, index);
+        self.stack.push(label_index);
+
+        for &target in self.graph[&label_index].iter() {
+            if !self.index.contains_key(&target) {
+                self.visit(target);
+                let low_link = self.low_link[&label_index].min(self.low_link[&target]);
+                self.low_link.insert(label_index, low_link);
+            } else if self.stack.contains(&target) {
+                let low_link = self.low_link[&label_index].min(self.index[&target]);
+                self.low_link.insert(label_index, low_link);
+            }
+        }
+
+        // the rule is the root of its group, everything above it on the stack belongs to the group
+        if self.low_link[&label_index] == index {
+            let mut component = Vec::new();
+            loop {
+                let member = self.stack.pop().expect("Stack should not be empty.");
+                component.push(member);
+                if member == label_index {
+                    break;
+                }
+            }
+            component.reverse();
+            self.components.push(component);
+        }
+    }
+}
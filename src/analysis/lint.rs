@@ -0,0 +1,299 @@
+use super::first::left_recursive_rules;
+use super::graph::rule_graph;
+use crate::bnf::alternation::Alternation;
+use crate::bnf::element::Element;
+use crate::bnf::grammar::Grammar;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+// the checks run on a grammar, in the order they are reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lint {
+    // a label is used but has no rule
+    UndefinedRule,
+    // a rule can't be reached from the entrypoint
+    UnusedRule,
+    // a rule has the same alternative twice
+    DuplicateAlternative,
+    // an ε next to other elements, where it does nothing
+    EmptyInSequence,
+    // an alternative derives no string, because of an empty range or of a rule recursing forever, which
+    // is unrelated to the order of the alternatives since the parser backtracks into all of them
+    UnproductiveAlternative,
+    // a rule can reach itself without consuming anything, which overflows the stack of the parser
+    LeftRecursion,
+}
+
+pub const LINTS: [Lint; 6] = [
+    Lint::UndefinedRule,
+    Lint::UnusedRule,
+    Lint::DuplicateAlternative,
+    Lint::EmptyInSequence,
+    Lint::UnproductiveAlternative,
+    Lint::LeftRecursion,
+];
+
+impl Lint {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Lint::UndefinedRule => "undefined-rule",
+            Lint::UnusedRule => "unused-rule",
+            Lint::DuplicateAlternative => "duplicate-alternative",
+            Lint::EmptyInSequence => "empty-in-sequence",
+            Lint::UnproductiveAlternative => "unproductive-alternative",
+            Lint::LeftRecursion => "left-recursion",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Lint> {
+        LINTS.into_iter().find(|lint| lint.name() == name)
+    }
+
+    // the grammars the parser can't use are denied, the others only get warnings
+    pub fn default_severity(&self) -> Severity {
+        match self {
+            Lint::UndefinedRule | Lint::LeftRecursion => Severity::Deny,
+            _ => Severity::Warn,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Allow,
+    Warn,
+    Deny,
+}
+
+impl Severity {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Severity::Allow => "allow",
+            Severity::Warn => "warn",
+            Severity::Deny => "deny",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Severity> {
+        [Severity::Allow, Severity::Warn, Severity::Deny]
+            .into_iter()
+            .find(|severity| severity.name() == name)
+    }
+}
+
+// the severity of every lint, the default one unless changed
+#[derive(Debug, Clone)]
+pub struct Severities {
+    severities: HashMap<Lint, Severity>,
+}
+
+#[derive(Debug, Clone)]
+pub enum ConfigError {
+    // the line isn't `lint = severity`
+    Syntax { line: usize },
+    UnknownLint { line: usize, name: String },
+    UnknownSeverity { line: usize, name: String },
+}
+
+impl Default for Severities {
+    fn default() -> Severities {
+        Severities {
+            severities: LINTS
+                .into_iter()
+                .map(|lint| (lint, lint.default_severity()))
+                .collect(),
+        }
+    }
+}
+
+impl Severities {
+    pub fn get(&self, lint: Lint) -> Severity {
+        self.severities[&lint]
+    }
+
+    pub fn set(&mut self, lint: Lint, severity: Severity) {
+        self.severities.insert(lint, severity);
+    }
+
+    // reads lines like `unused-rule = allow`, the `#` starting comments, so the file can also be
+    // written as TOML with the severities quoted
+    pub fn read_config(&mut self, config: &str) -> Result<(), ConfigError> {
+        for (i, line) in config.lines().enumerate() {
+            let line_number = i + 1;
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let Some((name, severity)) = line.split_once('=') else {
+                return Err(ConfigError::Syntax { line: line_number });
+            };
+            let unquote = |text: &str| text.trim().trim_matches('"').to_string();
+            let (name, severity) = (unquote(name), unquote(severity));
+            let Some(lint) = Lint::from_name(&name) else {
+                return Err(ConfigError::UnknownLint {
+                    line: line_number,
+                    name,
+                });
+            };
+            let Some(severity) = Severity::from_name(&severity) else {
+                return Err(ConfigError::UnknownSeverity {
+                    line: line_number,
+                    name: severity,
+                });
+            };
+            self.set(lint, severity);
+        }
+        Ok(())
+    }
+}
+
+// a lint that fired, on a rule and possibly on one of its alternatives (counted from 0)
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub lint: Lint,
+    pub rule: String,
+    pub alternative: Option<usize>,
+    // the alternative repeated by a duplicate one
+    pub original: Option<usize>,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let rule = &self.rule;
+        let alternative = self.alternative.map_or(0, |alternative| alternative + 1);
+        match self.lint {
+            Lint::UndefinedRule => write!(f, "<{rule}> is used but never defined"),
+            Lint::UnusedRule => write!(f, "<{rule}> can't be reached from the entrypoint"),
+            Lint::DuplicateAlternative => write!(
+                f,
+                "alternative {alternative} of <{rule}> repeats alternative {}",
+                self.original.map_or(0, |original| original + 1)
+            ),
+            Lint::EmptyInSequence => write!(
+                f,
+                "alternative {alternative} of <{rule}> has an ε next to other elements, where it does nothing"
+            ),
+            Lint::UnproductiveAlternative => {
+                write!(f, "alternative {alternative} of <{rule}> can never match")
+            }
+            Lint::LeftRecursion => write!(
+                f,
+                "<{rule}> is left recursive, the parser overflows its stack on it"
+            ),
+        }
+    }
+}
+
+// runs every lint on the grammar, which may come from `parse_grammar_lenient` and use undefined rules
+pub fn lint(grammar: &Grammar) -> Vec<Diagnostic> {
+    let label = |label_index: &usize| grammar.references[label_index].clone();
+    let diagnostic = |lint: Lint, label_index: &usize, alternative: Option<usize>| Diagnostic {
+        lint,
+        rule: label(label_index),
+        alternative,
+        original: None,
+    };
+    let mut diagnostics = Vec::new();
+
+    // the undefined rules get a rule matching nothing, so the other lints can look at the grammar
+    let mut undefined: Vec<usize> = grammar
+        .references
+        .keys()
+        .copied()
+        .filter(|label_index| !grammar.maps.contains_key(label_index))
+        .collect();
+    undefined.sort();
+    let mut completed = grammar.clone();
+    for label_index in undefined.iter() {
+        diagnostics.push(diagnostic(Lint::UndefinedRule, label_index, None));
+        completed.labels.push(*label_index);
+        completed
+            .maps
+            .insert(*label_index, Alternation { sequences: vec![] });
+    }
+
+    let reachable = reachable_rules(&completed);
+    let left_recursive = left_recursive_rules(&completed);
+    let productive = productive_rules(&completed, &undefined);
+    for label_index in grammar.labels.iter() {
+        if !reachable.contains(label_index) {
+            diagnostics.push(diagnostic(Lint::UnusedRule, label_index, None));
+        }
+        if left_recursive.contains(label_index) {
+            diagnostics.push(diagnostic(Lint::LeftRecursion, label_index, None));
+        }
+        let sequences = &grammar.maps[label_index].sequences;
+        for (i, sequence) in sequences.iter().enumerate() {
+            if let Some(original) = sequences[..i].iter().position(|other| other == sequence) {
+                diagnostics.push(Diagnostic {
+                    original: Some(original),
+                    ..diagnostic(Lint::DuplicateAlternative, label_index, Some(i))
+                });
+            }
+            if sequence.elements.len() > 1
+                && sequence
+                    .elements
+                    .iter()
+                    .any(|element| matches!(element, Element::Empty))
+            {
+                diagnostics.push(diagnostic(Lint::EmptyInSequence, label_index, Some(i)));
+            }
+            if !sequence
+                .elements
+                .iter()
+                .all(|element| element_productive(element, &productive))
+            {
+                diagnostics.push(diagnostic(
+                    Lint::UnproductiveAlternative,
+                    label_index,
+                    Some(i),
+                ));
+            }
+        }
+    }
+    diagnostics
+}
+
+fn reachable_rules(grammar: &Grammar) -> HashSet<usize> {
+    let graph = rule_graph(grammar);
+    let mut reachable = HashSet::new();
+    let mut stack = vec![grammar.entrypoint()];
+    while let Some(label_index) = stack.pop() {
+        if reachable.insert(label_index) {
+            stack.extend(graph[&label_index].iter().copied());
+        }
+    }
+    reachable
+}
+
+// the rules matching at least one string, the undefined ones being counted in since they are
+// already reported
+fn productive_rules(grammar: &Grammar, undefined: &[usize]) -> HashSet<usize> {
+    let mut productive: HashSet<usize> = undefined.iter().copied().collect();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for label_index in grammar.labels.iter() {
+            if !productive.contains(label_index)
+                && grammar.maps[label_index].sequences.iter().any(|sequence| {
+                    sequence
+                        .elements
+                        .iter()
+                        .all(|element| element_productive(element, &productive))
+                })
+            {
+                productive.insert(*label_index);
+                changed = true;
+            }
+        }
+    }
+    productive
+}
+
+fn element_productive(element: &Element, productive: &HashSet<usize>) -> bool {
+    match element {
+        Element::Empty | Element::Literal(_) => true,
+        Element::Range { start, end } => start <= end,
+        Element::Reference(target) => productive.contains(target),
+    }
+}
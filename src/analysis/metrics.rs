@@ -0,0 +1,195 @@
+use super::graph::{is_recursive, rule_graph, strongly_connected_components};
+use crate::bnf::element::Element;
+use crate::bnf::grammar::Grammar;
+use crate::json::write_string;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+#[derive(Debug, Clone)]
+pub struct Metrics {
+    pub rules: usize,
+    // the number of alternatives of every rule, in order of definition
+    pub alternatives: Vec<(String, usize)>,
+    pub max_sequence_length: usize,
+    // the longest chain of references from the entrypoint, a recursive group counting as a single step
+    pub recursion_depth: usize,
+    // distinct literals and ranges
+    pub terminals: usize,
+    pub range_characters: usize,
+    pub nullable_rules: usize,
+    pub components: usize,
+    // the groups of rules that can reference themselves
+    pub recursive_components: Vec<Vec<String>>,
+}
+
+impl Grammar {
+    pub fn metrics(&self) -> Metrics {
+        let label = |label_index: &usize| self.references[label_index].clone();
+
+        let mut max_sequence_length = 0;
+        let mut terminals: HashSet<&Element> = HashSet::new();
+        let mut ranges: Vec<(u32, u32)> = Vec::new();
+        for alternation in self.maps.values() {
+            for sequence in alternation.sequences.iter() {
+                max_sequence_length = max_sequence_length.max(sequence.elements.len());
+                for element in sequence.elements.iter() {
+                    match element {
+                        Element::Literal(_) => {
+                            terminals.insert(element);
+                        }
+                        Element::Range { start, end } => {
+                            terminals.insert(element);
+                            ranges.push((*start as u32, *end as u32));
+                        }
+                        Element::Empty | Element::Reference(_) => {}
+                    }
+                }
+            }
+        }
+
+        // the components come before the ones referencing them, so their depth is always known
+        let graph = rule_graph(self);
+        let components = strongly_connected_components(self);
+        let mut component_of = HashMap::new();
+        for (i, component) in components.iter().enumerate() {
+            for label_index in component.iter() {
+                component_of.insert(*label_index, i);
+            }
+        }
+        let mut depths: Vec<usize> = Vec::with_capacity(components.len());
+        for (i, component) in components.iter().enumerate() {
+            let mut depth = 0;
+            for label_index in component.iter() {
+                for target in graph[label_index].iter() {
+                    let target_component = component_of[target];
+                    if target_component != i {
+                        depth = depth.max(depths[target_component]);
+                    }
+                }
+            }
+            depths.push(depth + 1);
+        }
+
+        Metrics {
+            rules: self.labels.len(),
+            alternatives: self
+                .labels
+                .iter()
+                .map(|label_index| (label(label_index), self.maps[label_index].sequences.len()))
+                .collect(),
+            max_sequence_length,
+            recursion_depth: depths[component_of[&self.entrypoint()]],
+            terminals: terminals.len(),
+            range_characters: covered_characters(ranges),
+            nullable_rules: self.nullable_labels().len(),
+            components: components.len(),
+            recursive_components: components
+                .iter()
+                .filter(|component| is_recursive(&graph, component))
+                .map(|component| component.iter().map(label).collect())
+                .collect(),
+        }
+    }
+}
+
+impl Metrics {
+    fn alternatives_summary(&self) -> (usize, usize, f64) {
+        let counts = self.alternatives.iter().map(|(_, count)| *count);
+        let min = counts.clone().min().unwrap_or(0);
+        let max = counts.clone().max().unwrap_or(0);
+        let average = counts.sum::<usize>() as f64 / self.alternatives.len().max(1) as f64;
+        (min, max, average)
+    }
+
+    pub fn write_text(&self, output: &mut dyn Write) -> std::fmt::Result {
+        let (min, max, average) = self.alternatives_summary();
+        writeln!(output, "rules: {}", self.rules)?;
+        writeln!(
+            output,
+            "alternatives per rule: min {min}, max {max}, average {average:.2}"
+        )?;
+        for (label, count) in self.alternatives.iter() {
+            writeln!(output, "  <{label}>: {count}")?;
+        }
+        writeln!(output, "max sequence length: {}", self.max_sequence_length)?;
+        writeln!(output, "recursion depth: {}", self.recursion_depth)?;
+        writeln!(output, "terminals: {}", self.terminals)?;
+        writeln!(
+            output,
+            "characters covered by ranges: {}",
+            self.range_characters
+        )?;
+        writeln!(output, "nullable rules: {}", self.nullable_rules)?;
+        writeln!(output, "strongly connected components: {}", self.components)?;
+        for component in self.recursive_components.iter() {
+            let labels: Vec<String> = component.iter().map(|label| format!("<{label}>")).collect();
+            writeln!(output, "  recursive: {}", labels.join(" "))?;
+        }
+        Ok(())
+    }
+
+    pub fn write_json(&self, output: &mut dyn Write) -> std::fmt::Result {
+        let (min, max, average) = self.alternatives_summary();
+        write!(output, "{{\"rules\":{},\"alternatives\":{{", self.rules)?;
+        for (i, (label, count)) in self.alternatives.iter().enumerate() {
+            if i != 0 {
+                write!(output, ",")?;
+            }
+            write_string(output, label)?;
+            write!(output, ":{count}")?;
+        }
+        write!(
+            output,
+            "}},\"min_alternatives\":{min},\"max_alternatives\":{max},\"average_alternatives\":{average:.2}"
+        )?;
+        write!(
+            output,
+            ",\"max_sequence_length\":{},\"recursion_depth\":{},\"terminals\":{},\"range_characters\":{},\"nullable_rules\":{},\"components\":{}",
+            self.max_sequence_length,
+            self.recursion_depth,
+            self.terminals,
+            self.range_characters,
+            self.nullable_rules,
+            self.components
+        )?;
+        write!(output, ",\"recursive_components\":[")?;
+        for (i, component) in self.recursive_components.iter().enumerate() {
+            if i != 0 {
+                write!(output, ",")?;
+            }
+            write!(output, "[")?;
+            for (j, label) in component.iter().enumerate() {
+                if j != 0 {
+                    write!(output, ",")?;
+                }
+                write_string(output, label)?;
+            }
+            write!(output, "]")?;
+        }
+        writeln!(output, "]}}")
+    }
+}
+
+fn covered_characters(mut ranges: Vec<(u32, u32)>) -> usize {
+    // merge the overlapping ranges before counting, and leave the surrogates and the empty ranges out
+    ranges.retain(|(start, end)| start <= end);
+    ranges.sort();
+    let mut merged: Vec<(u32, u32)> = Vec::new();
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 + 1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+        .iter()
+        .map(|&(start, end)| {
+            let surrogates = if start <= 0xDFFF && end >= 0xD800 {
+                end.min(0xDFFF) - start.max(0xD800) + 1
+            } else {
+                0
+            };
+            (end - start + 1 - surrogates) as usize
+        })
+        .sum()
+}
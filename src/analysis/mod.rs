@@ -0,0 +1,5 @@
+pub mod equivalence;
+pub mod first;
+pub mod graph;
+pub mod lint;
+pub mod metrics;
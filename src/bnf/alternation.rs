@@ -1,6 +1,7 @@
 use super::sequence::{parse_sequence, Sequence};
+use super::symbols::{parse_single_newline, parse_spacings};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Alternation {
     pub sequences: Vec<Sequence>,
 }
@@ -22,15 +23,21 @@ impl super::format::Format for Alternation {
     }
 }
 
-fn parse_alternation_symbol(chars: &Vec<char>, index: usize) -> Result<usize, ()> {
+fn parse_alternation_symbol(chars: &[char], index: usize) -> Result<usize, ()> {
     let Some('|') = chars.get(index) else {
         return Err(());
     };
     Ok(index + 1)
 }
 
+// a long rule can go on over the next lines, each one starting with the | of an alternative
+fn parse_continuation(chars: &[char], index: usize) -> Result<usize, ()> {
+    let index = parse_single_newline(chars, index)?;
+    parse_alternation_symbol(chars, parse_spacings(chars, index))
+}
+
 pub fn parse_alternations(
-    chars: &Vec<char>,
+    chars: &[char],
     mut index: usize,
     labels: &mut std::collections::HashMap<usize, String>,
     labels_reverse: &mut std::collections::HashMap<String, usize>,
@@ -39,13 +46,15 @@ pub fn parse_alternations(
     while let Ok((new_index, sequence)) = parse_sequence(chars, index, labels, labels_reverse) {
         sequences.push(sequence);
         index = new_index;
-        match parse_alternation_symbol(chars, new_index) {
+        match parse_alternation_symbol(chars, new_index)
+            .or_else(|_| parse_continuation(chars, new_index))
+        {
             Ok(new_index) => index = new_index,
             Err(_) => break,
         }
     }
     if sequences.is_empty() {
-        return Err(());
+        Err(())
     } else {
         Ok((index, Alternation { sequences }))
     }
@@ -0,0 +1,151 @@
+use super::grammar::{ChoiceState, Event, Grammar, Observer};
+use std::fmt::Write;
+
+// where the debugger pauses whatever it was asked to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Breakpoint {
+    // when the parser goes into the rule
+    Rule(usize),
+    // when the parser arrives at the offset in the input, moving forward or backtracking
+    Offset(usize),
+}
+
+// what to do after a pause
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    // pauses at the next step
+    StepInto,
+    // pauses at the next step that isn't inside the reference entered, or inside the current rule's
+    // references
+    StepOver,
+    // pauses once the parser is out of the current rule
+    StepOut,
+    // pauses at the next breakpoint
+    Continue,
+    // stops the parse, which then fails with `Error::Stopped`
+    Quit,
+}
+
+// the step the parser is paused at
+pub struct Pause<'a, 'b> {
+    pub event: &'b Event<'a>,
+    pub depth: usize,
+    pub choices: &'b [ChoiceState],
+    // the breakpoint that caused the pause, if any
+    pub breakpoint: Option<Breakpoint>,
+}
+
+// pauses the parser at the breakpoints and after the steps asked by the handler, the handler being
+// given the pause and the breakpoints, which it can change, and telling what to do next
+pub struct Debugger<F> {
+    pub breakpoints: Vec<Breakpoint>,
+    handler: F,
+    command: Command,
+    // the depth the last command was given at
+    depth: usize,
+    // where the previous step left the parser in the input
+    position: Option<usize>,
+}
+
+impl<F> Debugger<F>
+where
+    F: FnMut(&Pause, &mut Vec<Breakpoint>) -> Command,
+{
+    // pauses at the first breakpoint, or at the very first step when there are none
+    pub fn new(breakpoints: Vec<Breakpoint>, handler: F) -> Self {
+        let command = if breakpoints.is_empty() {
+            Command::StepInto
+        } else {
+            Command::Continue
+        };
+        Debugger {
+            breakpoints,
+            handler,
+            command,
+            depth: 0,
+            position: None,
+        }
+    }
+
+    fn breakpoint(&self, event: &Event, position: usize) -> Option<Breakpoint> {
+        self.breakpoints
+            .iter()
+            .find(|breakpoint| match (breakpoint, event) {
+                (Breakpoint::Rule(rule), Event::Enter { label_index, .. }) => rule == label_index,
+                (Breakpoint::Offset(offset), _) => {
+                    *offset == position && self.position != Some(position)
+                }
+                _ => false,
+            })
+            .copied()
+    }
+}
+
+impl<'a, F> Observer<'a> for Debugger<F>
+where
+    F: FnMut(&Pause, &mut Vec<Breakpoint>) -> Command,
+{
+    fn event(&mut self, event: &Event<'a>, depth: usize, choices: &[ChoiceState]) {
+        let position = event.position();
+        let breakpoint = self.breakpoint(event, position);
+        self.position = Some(position);
+
+        let step = match self.command {
+            Command::StepInto => true,
+            Command::StepOver => depth <= self.depth,
+            Command::StepOut => depth < self.depth,
+            Command::Continue | Command::Quit => false,
+        };
+        if self.command == Command::Quit || (!step && breakpoint.is_none()) {
+            return;
+        }
+
+        let pause = Pause {
+            event,
+            depth,
+            choices,
+            breakpoint,
+        };
+        self.command = (self.handler)(&pause, &mut self.breakpoints);
+        // stepping over a reference being entered skips all of it, like stepping out of it
+        self.depth = match (self.command, event) {
+            (Command::StepOver, Event::Enter { .. }) => depth - 1,
+            _ => depth,
+        };
+    }
+
+    fn stopped(&self) -> bool {
+        self.command == Command::Quit
+    }
+}
+
+// writes the choice stack, one reference per line from the entrypoint, with the alternative the
+// parser is on for it, where it started in the input, and where the reference is in its parent
+pub fn write_choices(
+    output: &mut dyn Write,
+    grammar: &Grammar,
+    choices: &[ChoiceState],
+) -> std::fmt::Result {
+    for (i, choice) in choices.iter().enumerate() {
+        write!(
+            output,
+            "#{i} {}<{}> alternative {} at {}",
+            "  ".repeat(choice.depth),
+            grammar.references[&choice.destination_label_index],
+            choice.destination_alternation_index + 1,
+            choice.input_index
+        )?;
+        // the first choice is the entrypoint, which isn't referenced from anywhere
+        if i != 0 {
+            write!(
+                output,
+                ", from <{}> alternative {} element {}",
+                grammar.references[&choice.source_label_index],
+                choice.source_alternation_index + 1,
+                choice.source_sequence_index + 1
+            )?;
+        }
+        writeln!(output)?;
+    }
+    Ok(())
+}
@@ -1,4 +1,4 @@
-pub fn parse_empty_symbol(chars: &Vec<char>, index: usize) -> Result<usize, ()> {
+pub fn parse_empty_symbol(chars: &[char], index: usize) -> Result<usize, ()> {
     let Some('ε') = chars.get(index) else {
         return Err(());
     };
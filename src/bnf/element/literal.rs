@@ -1,4 +1,4 @@
-fn parse_literal_start(chars: &Vec<char>, index: usize) -> Result<(usize, usize), ()> {
+fn parse_literal_start(chars: &[char], index: usize) -> Result<(usize, usize), ()> {
     // count how many # there are before the string
     let mut escape_length = 0;
     while let Some('#') = chars.get(index + escape_length) {
@@ -14,7 +14,7 @@ fn parse_literal_start(chars: &Vec<char>, index: usize) -> Result<(usize, usize)
     Ok((index + escape_length + 1, escape_length))
 }
 
-fn parse_literal_end(chars: &Vec<char>, index: usize, escape_length: usize) -> Result<usize, ()> {
+fn parse_literal_end(chars: &[char], index: usize, escape_length: usize) -> Result<usize, ()> {
     // check that there is a "
     let Some('"') = chars.get(index) else {
         return Err(());
@@ -31,7 +31,7 @@ fn parse_literal_end(chars: &Vec<char>, index: usize, escape_length: usize) -> R
     Ok(index + 1 + escape_length)
 }
 
-pub fn parse_literal(chars: &Vec<char>, index: usize) -> Result<(usize, String), ()> {
+pub fn parse_literal(chars: &[char], index: usize) -> Result<(usize, String), ()> {
     // parse the start of the string
     let (mut index, escape_length) = parse_literal_start(chars, index)?;
 
@@ -54,3 +54,21 @@ pub fn parse_literal(chars: &Vec<char>, index: usize) -> Result<(usize, String),
         }
     }
 }
+
+// writes the literal with as few # as possible: none unless it contains a ", then one more than
+// the longest run of # following a " inside it; the empty literal can't be written, it is ε
+pub fn write_literal(output: &mut dyn std::fmt::Write, literal: &str) -> std::fmt::Result {
+    if literal.is_empty() {
+        return write!(output, "ε");
+    }
+    let chars: Vec<char> = literal.chars().collect();
+    let mut escape_length = 0;
+    for (i, c) in chars.iter().enumerate() {
+        if *c == '"' {
+            let hashes = chars[i + 1..].iter().take_while(|c| **c == '#').count();
+            escape_length = escape_length.max(hashes + 1);
+        }
+    }
+    let hashes = "#".repeat(escape_length);
+    write!(output, "{hashes}\"{literal}\"{hashes}")
+}
@@ -4,11 +4,11 @@ pub mod range;
 pub mod reference;
 
 use empty::parse_empty_symbol;
-use literal::parse_literal;
+use literal::{parse_literal, write_literal};
 use range::parse_range;
 use reference::parse_reference;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Element {
     Empty,
     Range { start: char, end: char },
@@ -24,8 +24,12 @@ impl super::format::Format for Element {
     ) -> std::fmt::Result {
         match self {
             Element::Empty => write!(output, "ε"),
-            Element::Range { start, end } => write!(output, r#""{}"..="{}""#, start, end),
-            Element::Literal(s) => write!(output, r#""{s}""#),
+            Element::Range { start, end } => {
+                write_literal(output, &start.to_string())?;
+                write!(output, "..=")?;
+                write_literal(output, &end.to_string())
+            }
+            Element::Literal(s) => write_literal(output, s),
             Element::Reference(s) => {
                 let label = grammar.references.get(s).expect("Label should exist.");
                 write!(output, "<{label}>")
@@ -35,7 +39,7 @@ impl super::format::Format for Element {
 }
 
 pub fn parse_element(
-    chars: &Vec<char>,
+    chars: &[char],
     index: usize,
     labels: &mut std::collections::HashMap<usize, String>,
     labels_reverse: &mut std::collections::HashMap<String, usize>,
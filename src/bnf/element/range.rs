@@ -1,6 +1,6 @@
 use super::literal::parse_literal;
 
-fn parse_range_symbol(chars: &Vec<char>, index: usize) -> Result<usize, ()> {
+fn parse_range_symbol(chars: &[char], index: usize) -> Result<usize, ()> {
     let Some('.') = chars.get(index) else {
         return Err(());
     };
@@ -13,16 +13,16 @@ fn parse_range_symbol(chars: &Vec<char>, index: usize) -> Result<usize, ()> {
     Ok(index + 3)
 }
 
-pub fn parse_range(chars: &Vec<char>, index: usize) -> Result<(usize, char, char), ()> {
+pub fn parse_range(chars: &[char], index: usize) -> Result<(usize, char, char), ()> {
     let (index, first_string) = parse_literal(chars, index)?;
-    if first_string.len() != 1 {
+    if first_string.chars().count() != 1 {
         return Err(());
     }
 
     let index = parse_range_symbol(chars, index)?;
 
     let (index, second_string) = parse_literal(chars, index)?;
-    if second_string.len() != 1 {
+    if second_string.chars().count() != 1 {
         return Err(());
     }
 
@@ -30,11 +30,11 @@ pub fn parse_range(chars: &Vec<char>, index: usize) -> Result<(usize, char, char
         index,
         first_string
             .chars()
-            .nth(0)
+            .next()
             .expect("First char should be defined."),
         second_string
             .chars()
-            .nth(0)
+            .next()
             .expect("Second char should be defined."),
     ))
 }
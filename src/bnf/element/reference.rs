@@ -1,13 +1,13 @@
 use std::collections::HashMap;
 
-fn parse_openbra_symbol(chars: &Vec<char>, index: usize) -> Result<usize, ()> {
+fn parse_openbra_symbol(chars: &[char], index: usize) -> Result<usize, ()> {
     let Some('<') = chars.get(index) else {
         return Err(());
     };
     Ok(index + 1)
 }
 
-fn parse_closebra_symbol(chars: &Vec<char>, index: usize) -> Result<usize, ()> {
+fn parse_closebra_symbol(chars: &[char], index: usize) -> Result<usize, ()> {
     let Some('>') = chars.get(index) else {
         return Err(());
     };
@@ -15,7 +15,7 @@ fn parse_closebra_symbol(chars: &Vec<char>, index: usize) -> Result<usize, ()> {
 }
 
 pub fn parse_reference(
-    chars: &Vec<char>,
+    chars: &[char],
     index: usize,
     labels: &mut HashMap<usize, String>,
     labels_reverse: &mut HashMap<String, usize>,
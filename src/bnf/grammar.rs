@@ -2,7 +2,7 @@ use super::alternation::{parse_alternations, Alternation};
 use super::element::reference::parse_reference;
 use super::element::Element;
 use super::symbols::{parse_newlines, parse_spacings};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 #[derive(Debug, Clone)]
 pub struct Grammar {
@@ -16,21 +16,121 @@ impl super::format::Format for Grammar {
         for label_index in self.labels.iter() {
             let label = self
                 .references
-                .get(&label_index)
+                .get(label_index)
                 .expect("Label should exist.");
             let alternation = self
                 .maps
-                .get(&label_index)
+                .get(label_index)
                 .expect("Alternation should exist.");
             write!(output, "<{label}> ::= ",)?;
             alternation.format(output, grammar)?;
-            write!(output, "\n",)?;
+            writeln!(output)?;
         }
         Ok(())
     }
 }
 
-fn parse_match_symbol(chars: &Vec<char>, index: usize) -> Result<usize, ()> {
+impl Grammar {
+    pub fn entrypoint(&self) -> usize {
+        // the first label defined is the entrypoint
+        self.labels[0]
+    }
+
+    pub fn label_index(&self, label: &str) -> Option<usize> {
+        self.references
+            .iter()
+            .find(|(_, existing)| existing.as_str() == label)
+            .map(|(&label_index, _)| label_index)
+    }
+
+    pub fn fresh_label(&self, base: &str) -> String {
+        // try the base name first, then append an increasing counter until the name is free
+        if self.label_index(base).is_none() {
+            return base.to_string();
+        }
+        let mut counter = 2;
+        loop {
+            let label = format!("{base}-{counter}");
+            if self.label_index(&label).is_none() {
+                return label;
+            }
+            counter += 1;
+        }
+    }
+
+    pub fn add_rule(&mut self, label: String, alternation: Alternation) -> usize {
+        let label_index = self.next_label_index();
+        self.references.insert(label_index, label);
+        self.labels.push(label_index);
+        self.maps.insert(label_index, alternation);
+        label_index
+    }
+
+    pub fn add_rule_after(
+        &mut self,
+        after: usize,
+        label: String,
+        alternation: Alternation,
+    ) -> usize {
+        let label_index = self.next_label_index();
+        let position = self
+            .labels
+            .iter()
+            .position(|&existing| existing == after)
+            .map_or(self.labels.len(), |position| position + 1);
+        self.references.insert(label_index, label);
+        self.labels.insert(position, label_index);
+        self.maps.insert(label_index, alternation);
+        label_index
+    }
+
+    pub fn set_entrypoint(&mut self, label_index: usize) {
+        self.labels.retain(|&existing| existing != label_index);
+        self.labels.insert(0, label_index);
+    }
+
+    pub fn remove_rule(&mut self, label_index: usize) {
+        self.references.remove(&label_index);
+        self.labels.retain(|&existing| existing != label_index);
+        self.maps.remove(&label_index);
+    }
+
+    fn next_label_index(&self) -> usize {
+        // index 0 is reserved for the entrypoint placeholder, so we never hand it out
+        self.references.keys().max().copied().unwrap_or(0) + 1
+    }
+
+    pub fn nullable_labels(&self) -> HashSet<usize> {
+        // iterate until a fixed point: a rule is nullable if one of its sequences only contains
+        // empty elements or references to rules already known to be nullable
+        let mut nullable = HashSet::new();
+        loop {
+            let mut changed = false;
+            for label_index in self.labels.iter() {
+                if nullable.contains(label_index) {
+                    continue;
+                }
+                let alternation = self
+                    .maps
+                    .get(label_index)
+                    .expect("Alternation should exist.");
+                if alternation
+                    .sequences
+                    .iter()
+                    .any(|sequence| sequence.is_nullable(&nullable))
+                {
+                    nullable.insert(*label_index);
+                    changed = true;
+                }
+            }
+            if !changed {
+                return nullable;
+            }
+        }
+    }
+}
+
+fn parse_match_symbol(chars: &[char], index: usize) -> Result<usize, ()> {
     let Some(':') = chars.get(index) else {
         return Err(());
     };
@@ -43,8 +143,8 @@ fn parse_match_symbol(chars: &Vec<char>, index: usize) -> Result<usize, ()> {
     Ok(index + 3)
 }
 
-fn parse_expression(
-    chars: &Vec<char>,
+pub fn parse_expression(
+    chars: &[char],
     index: usize,
     labels: &mut HashMap<usize, String>,
     labels_reverse: &mut HashMap<String, usize>,
@@ -56,7 +156,83 @@ fn parse_expression(
     Ok((index, label_index, alternations))
 }
 
-pub fn parse_grammar(chars: &Vec<char>, mut index: usize) -> Result<Grammar, ()> {
+pub fn parse_grammar(chars: &[char], index: usize) -> Result<Grammar, ()> {
+    parse_grammar_checked(chars, index).map_err(|_| ())
+}
+
+// why a grammar is invalid, the indices being positions in the characters of the grammar
+#[derive(Debug, Clone)]
+pub enum GrammarError {
+    // nothing from here could be parsed as a rule
+    Syntax { index: usize },
+    // the rule starting here was already defined
+    DuplicateRule { index: usize, label: String },
+    // the label is used here but never defined
+    UndefinedRule { index: usize, label: String },
+    NoRules,
+}
+
+impl std::fmt::Display for GrammarError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            GrammarError::Syntax { .. } => {
+                write!(f, "invalid rule, expected `<label> ::= alternatives`")
+            }
+            GrammarError::DuplicateRule { label, .. } => write!(f, "<{label}> is already defined"),
+            GrammarError::UndefinedRule { label, .. } => {
+                write!(f, "<{label}> is used but never defined")
+            }
+            GrammarError::NoRules => write!(f, "the grammar has no rules"),
+        }
+    }
+}
+
+impl GrammarError {
+    pub fn index(&self) -> usize {
+        match self {
+            GrammarError::Syntax { index }
+            | GrammarError::DuplicateRule { index, .. }
+            | GrammarError::UndefinedRule { index, .. } => *index,
+            GrammarError::NoRules => 0,
+        }
+    }
+
+    // the line and column of the error, both counted from 1
+    pub fn position(&self, chars: &[char]) -> (usize, usize) {
+        let index = self.index().min(chars.len());
+        let line_start = chars[..index]
+            .iter()
+            .rposition(|&c| c == '\n')
+            .map_or(0, |position| position + 1);
+        let line = chars[..index].iter().filter(|&&c| c == '\n').count() + 1;
+        (line, index - line_start + 1)
+    }
+}
+
+// same as `parse_grammar`, telling where the grammar is invalid
+pub fn parse_grammar_checked(chars: &[char], index: usize) -> Result<Grammar, GrammarError> {
+    let grammar = parse_grammar_lenient(chars, index)?;
+
+    // if the number of labels doesn't match the number of references, then the grammar is invalid
+    if grammar.references.len() != grammar.labels.len() {
+        // the references are numbered in order of appearance, the first undefined one is reported
+        let label_index = (1..=grammar.references.len())
+            .find(|label_index| !grammar.labels.contains(label_index))
+            .expect("A reference should be undefined.");
+        let label = grammar.references[&label_index].clone();
+        let pattern: Vec<char> = format!("<{label}>").chars().collect();
+        let index = chars
+            .windows(pattern.len())
+            .position(|window| window == pattern.as_slice())
+            .unwrap_or(0);
+        return Err(GrammarError::UndefinedRule { index, label });
+    }
+    Ok(grammar)
+}
+
+// same as `parse_grammar_checked`, but the references to undefined rules are kept: they are in
+// `references` without being in `labels` nor `maps`, so the grammar can be analysed but not parsed with
+pub fn parse_grammar_lenient(chars: &[char], mut index: usize) -> Result<Grammar, GrammarError> {
     let mut references = HashMap::new();
     let mut references_reversed = HashMap::new();
     let mut labels = Vec::new();
@@ -68,7 +244,10 @@ pub fn parse_grammar(chars: &Vec<char>, mut index: usize) -> Result<Grammar, ()>
     {
         // check if the label was already defined
         if labels.contains(&label_index) {
-            return Err(());
+            return Err(GrammarError::DuplicateRule {
+                index: parse_spacings(chars, index),
+                label: references[&label_index].clone(),
+            });
         } else {
             labels.push(label_index);
         }
@@ -83,17 +262,12 @@ pub fn parse_grammar(chars: &Vec<char>, mut index: usize) -> Result<Grammar, ()>
 
     // if we didn't parse all the input characters, then the grammar is invalid
     if chars.len() != index {
-        return Err(());
+        return Err(GrammarError::Syntax { index });
     }
 
     // if there are no labels, then the grammar is invalid
     if labels.is_empty() {
-        return Err(());
-    }
-
-    // if the number of labels doesn't match the number of references, then the grammar is invalid
-    if references.len() != labels.len() {
-        return Err(());
+        return Err(GrammarError::NoRules);
     }
 
     Ok(Grammar {
@@ -103,25 +277,110 @@ pub fn parse_grammar(chars: &Vec<char>, mut index: usize) -> Result<Grammar, ()>
     })
 }
 
+// a reference the parser went into, with the alternative it is trying for it
 #[derive(Debug, Clone, Copy)]
 pub struct ChoiceState {
-    depth: usize,
-    source_label_index: usize,
-    source_alternation_index: usize,
-    source_sequence_index: usize,
-    destination_label_index: usize,
-    destination_alternation_index: usize,
-    input_index: usize,
+    pub depth: usize,
+    pub source_label_index: usize,
+    pub source_alternation_index: usize,
+    pub source_sequence_index: usize,
+    pub destination_label_index: usize,
+    pub destination_alternation_index: usize,
+    pub input_index: usize,
 }
 
 #[derive(Debug, Clone, Copy)]
 pub enum Error {
     StackOverflow,
     NoMatch,
+    // the observer stopped the parse
+    Stopped,
+}
+
+// what the parser does, reported to an `Observer` as it goes, `input_index` being where in the input
+// characters it happens
+#[derive(Debug, Clone, Copy)]
+pub enum Event<'a> {
+    // goes into a reference, or into the entrypoint when the parse starts
+    Enter {
+        label_index: usize,
+        input_index: usize,
+    },
+    // tries an alternative of the rule (counted from 0), from its first element
+    Attempt {
+        label_index: usize,
+        alternative: usize,
+        input_index: usize,
+    },
+    // a literal, a range or ε matched the input from `start` to `end`
+    Match {
+        element: &'a Element,
+        start: usize,
+        end: usize,
+    },
+    // a literal or a range didn't match the input
+    Mismatch {
+        element: &'a Element,
+        input_index: usize,
+    },
+    // the rule matched the input from `start` to `end` with the alternative
+    Exit {
+        label_index: usize,
+        alternative: usize,
+        start: usize,
+        end: usize,
+    },
+    // all the alternatives of the rule failed
+    Fail {
+        label_index: usize,
+        input_index: usize,
+    },
+    // goes back to a rule entered before, to try its next alternative from where it started
+    Backtrack {
+        label_index: usize,
+        input_index: usize,
+    },
+}
+
+impl Event<'_> {
+    // where the parser is in the input after the step
+    pub fn position(&self) -> usize {
+        match *self {
+            Event::Enter { input_index, .. }
+            | Event::Attempt { input_index, .. }
+            | Event::Mismatch { input_index, .. }
+            | Event::Fail { input_index, .. }
+            | Event::Backtrack { input_index, .. } => input_index,
+            Event::Match { end, .. } | Event::Exit { end, .. } => end,
+        }
+    }
+}
+
+// receives the events of a parse, with the depth of the rule they happen in (the entrypoint being at
+// depth 1) and the choice stack at that point
+pub trait Observer<'a> {
+    fn event(&mut self, _event: &Event<'a>, _depth: usize, _choices: &[ChoiceState]) {}
+
+    // tells the parser to give up, checked before each step
+    fn stopped(&self) -> bool {
+        false
+    }
 }
 
+// observes nothing, for the plain parse
+impl Observer<'_> for () {}
+
 impl Grammar {
     pub fn parse(&self, input: &str) -> Result<Vec<ChoiceState>, Error> {
+        self.parse_observed(input, &mut ())
+    }
+
+    // same as `parse`, reporting every step of the parser to the observer
+    pub fn parse_observed<'a, O: Observer<'a>>(
+        &'a self,
+        input: &str,
+        observer: &mut O,
+    ) -> Result<Vec<ChoiceState>, Error> {
         let chars: Vec<char> = input.chars().collect();
 
         let mut choice_stack: Vec<ChoiceState> = vec![ChoiceState {
@@ -129,23 +388,36 @@ impl Grammar {
             source_label_index: 0, // this label doesn't exist, it's just a placeholder for the entrypoint
             source_alternation_index: 0,
             source_sequence_index: 0,
-            destination_label_index: 1, // the first label defined is the entrypoint
+            destination_label_index: self.entrypoint(),
             destination_alternation_index: 0,
             input_index: 0,
         }];
 
         let mut current_depth: usize = 1;
-        let mut current_label_index: usize = 1;
+        let mut current_label_index: usize = self.entrypoint();
         let mut current_alternation_index: usize = 0;
         let mut current_sequence_index: usize = 0;
         let mut current_input_index: usize = 0;
 
+        observer.event(
+            &Event::Enter {
+                label_index: current_label_index,
+                input_index: 0,
+            },
+            current_depth,
+            &choice_stack,
+        );
+
         'main_loop: loop {
             // check for stack overflow
             if choice_stack.len() > 1_000_000 {
                 return Err(Error::StackOverflow);
             }
 
+            if observer.stopped() {
+                return Err(Error::Stopped);
+            }
+
             // retrieve the current alternation
             let current_alternation = self
                 .maps
@@ -154,6 +426,15 @@ impl Grammar {
 
             // check that the current alternation index isn't out of bounds
             if current_alternation.sequences.len() <= current_alternation_index {
+                observer.event(
+                    &Event::Fail {
+                        label_index: current_label_index,
+                        input_index: current_input_index,
+                    },
+                    current_depth,
+                    &choice_stack,
+                );
+
                 // dump the previous choice
                 choice_stack
                     .pop()
@@ -176,6 +457,15 @@ impl Grammar {
                 current_sequence_index = 0;
                 current_input_index = choice.input_index;
 
+                observer.event(
+                    &Event::Backtrack {
+                        label_index: current_label_index,
+                        input_index: current_input_index,
+                    },
+                    current_depth,
+                    &choice_stack,
+                );
+
                 // continue the loop
                 continue 'main_loop;
             }
@@ -183,6 +473,19 @@ impl Grammar {
             // pull the sequence
             let current_sequence = &current_alternation.sequences[current_alternation_index];
 
+            // the first element of a sequence is only reached when the alternative is tried
+            if current_sequence_index == 0 {
+                observer.event(
+                    &Event::Attempt {
+                        label_index: current_label_index,
+                        alternative: current_alternation_index,
+                        input_index: current_input_index,
+                    },
+                    current_depth,
+                    &choice_stack,
+                );
+            }
+
             // check that the current sequence index isn't out of bounds
             // if we are out of bounds it means we completed the sequence
             if current_sequence.elements.len() <= current_sequence_index {
@@ -190,6 +493,16 @@ impl Grammar {
                 if current_depth == 1 {
                     // ... either we parsed all the input text, and we are done
                     if current_input_index == chars.len() {
+                        observer.event(
+                            &Event::Exit {
+                                label_index: current_label_index,
+                                alternative: current_alternation_index,
+                                start: 0,
+                                end: current_input_index,
+                            },
+                            current_depth,
+                            &choice_stack,
+                        );
                         return Ok(choice_stack);
                     }
 
@@ -210,6 +523,15 @@ impl Grammar {
                     current_sequence_index = 0;
                     current_input_index = last_choice.input_index;
 
+                    observer.event(
+                        &Event::Backtrack {
+                            label_index: current_label_index,
+                            input_index: current_input_index,
+                        },
+                        current_depth,
+                        &choice_stack,
+                    );
+
                     continue 'main_loop;
                 }
 
@@ -232,6 +554,17 @@ impl Grammar {
                 // reference to the choice one level up
                 let previous_choice = &choice_stack[x];
 
+                observer.event(
+                    &Event::Exit {
+                        label_index: current_label_index,
+                        alternative: current_alternation_index,
+                        start: previous_choice.input_index,
+                        end: current_input_index,
+                    },
+                    current_depth,
+                    &choice_stack,
+                );
+
                 current_depth = previous_choice.depth;
                 current_label_index = previous_choice.source_label_index;
                 current_alternation_index = previous_choice.source_alternation_index;
@@ -268,10 +601,19 @@ impl Grammar {
                     });
 
                     current_depth += 1;
-                    current_label_index = label_index.clone();
+                    current_label_index = *label_index;
                     current_alternation_index = 0;
                     current_sequence_index = 0;
 
+                    observer.event(
+                        &Event::Enter {
+                            label_index: current_label_index,
+                            input_index: current_input_index,
+                        },
+                        current_depth,
+                        &choice_stack,
+                    );
+
                     continue 'main_loop;
                 }
             };
@@ -279,11 +621,30 @@ impl Grammar {
             match match_result {
                 // if the match succeeded, then we update the input cursor and bump the sequence index
                 Ok(new_input_index) => {
+                    observer.event(
+                        &Event::Match {
+                            element: current_element,
+                            start: current_input_index,
+                            end: new_input_index,
+                        },
+                        current_depth,
+                        &choice_stack,
+                    );
+
                     current_input_index = new_input_index;
                     current_sequence_index += 1;
                 }
                 // if the match failed
                 Err(()) => {
+                    observer.event(
+                        &Event::Mismatch {
+                            element: current_element,
+                            input_index: current_input_index,
+                        },
+                        current_depth,
+                        &choice_stack,
+                    );
+
                     // ref to the last choice
                     let last_choice = choice_stack
                         .last_mut()
@@ -294,17 +655,26 @@ impl Grammar {
 
                     // move cursor to there
                     current_depth = last_choice.depth + 1;
-                    current_label_index = last_choice.destination_label_index.clone();
+                    current_label_index = last_choice.destination_label_index;
                     current_alternation_index = last_choice.destination_alternation_index;
                     current_sequence_index = 0;
                     current_input_index = last_choice.input_index;
+
+                    observer.event(
+                        &Event::Backtrack {
+                            label_index: current_label_index,
+                            input_index: current_input_index,
+                        },
+                        current_depth,
+                        &choice_stack,
+                    );
                 }
             }
         }
     }
 }
 
-fn match_range(start: &char, end: &char, chars: &Vec<char>, index: usize) -> Result<usize, ()> {
+fn match_range(start: &char, end: &char, chars: &[char], index: usize) -> Result<usize, ()> {
     if let Some(c) = chars.get(index) {
         if start <= c && c <= end {
             Ok(index + 1)
@@ -316,7 +686,7 @@ fn match_range(start: &char, end: &char, chars: &Vec<char>, index: usize) -> Res
     }
 }
 
-fn match_literal(literal: &str, chars: &Vec<char>, index: usize) -> Result<usize, ()> {
+fn match_literal(literal: &str, chars: &[char], index: usize) -> Result<usize, ()> {
     let mut i = 0;
     for c in literal.chars() {
         if let Some(&input_char) = chars.get(index + i) {
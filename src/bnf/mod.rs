@@ -1,6 +1,11 @@
 pub mod alternation;
+pub mod debugger;
 pub mod element;
 pub mod format;
 pub mod grammar;
+pub mod pretty;
+pub mod profile;
 pub mod sequence;
 pub mod symbols;
+pub mod trace;
+pub mod tree;
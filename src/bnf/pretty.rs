@@ -0,0 +1,55 @@
+use super::format::Format;
+use super::grammar::Grammar;
+use std::fmt::Write;
+
+pub struct Options {
+    // the longest line written, in characters, unless a single alternative is already longer
+    pub width: usize,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options { width: 100 }
+    }
+}
+
+// writes the grammar in its canonical layout: the ::= of all the rules aligned, and the
+// alternatives that don't fit in the width moved to continuation lines starting with their |
+// under the = of the ::=
+pub fn write_pretty(
+    grammar: &Grammar,
+    options: &Options,
+    output: &mut dyn Write,
+) -> std::fmt::Result {
+    let label_width = grammar
+        .labels
+        .iter()
+        .map(|label_index| grammar.references[label_index].chars().count() + 2)
+        .max()
+        .unwrap_or(0);
+    // where the first alternative starts, the continuation lines starting 2 characters before
+    let indent = label_width + " ::= ".len();
+
+    for label_index in grammar.labels.iter() {
+        let label = format!("<{}>", grammar.references[label_index]);
+        write!(output, "{label:<label_width$} ::=")?;
+        let mut length = label_width + " ::=".len();
+        for (i, sequence) in grammar.maps[label_index].sequences.iter().enumerate() {
+            let mut text = String::new();
+            sequence.format(&mut text, grammar)?;
+            let text_length = text.chars().count();
+            if i == 0 {
+                write!(output, " {text}")?;
+                length += 1 + text_length;
+            } else if length + " | ".len() + text_length <= options.width {
+                write!(output, " | {text}")?;
+                length += " | ".len() + text_length;
+            } else {
+                write!(output, "\n{}| {text}", " ".repeat(indent - 2))?;
+                length = indent + text_length;
+            }
+        }
+        writeln!(output)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,305 @@
+use super::element::Element;
+use super::grammar::{ChoiceState, Error, Event, Grammar, Observer};
+use crate::json::write_string;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+// what a rule, or one of its alternatives, cost the parser
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Counters {
+    // the times the rule was entered, or the alternative tried
+    pub entries: usize,
+    // the times the rule, or the alternative, matched
+    pub matches: usize,
+    // the literals and ranges compared to the input
+    pub comparisons: usize,
+    // the backtracks caused by a terminal that didn't match, or by all the alternatives failing
+    pub backtracks: usize,
+    // the time spent in the rule itself, its references not included
+    pub time: Duration,
+}
+
+// the counters of a parse, by rule and by alternative (counted from 0)
+#[derive(Debug, Clone)]
+pub struct Profile {
+    pub rules: HashMap<usize, Counters>,
+    pub alternatives: HashMap<(usize, usize), Counters>,
+    pub total: Duration,
+    // the rules the parser is in, from the entrypoint
+    stack: Vec<Frame>,
+    // the index of the choice each choice was entered from, following the choice stack
+    parents: Vec<usize>,
+    last: Instant,
+}
+
+// a rule the parser is in, with the alternative it is on and the choice that entered it
+#[derive(Debug, Clone, Copy)]
+struct Frame {
+    label_index: usize,
+    alternative: usize,
+    choice: usize,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Profile {
+            rules: HashMap::new(),
+            alternatives: HashMap::new(),
+            total: Duration::ZERO,
+            stack: Vec::new(),
+            parents: Vec::new(),
+            last: Instant::now(),
+        }
+    }
+}
+
+impl Profile {
+    // applies the change to the counters of the current rule and of its alternative
+    fn count(&mut self, change: impl Fn(&mut Counters)) {
+        if let Some(frame) = self.stack.last() {
+            change(self.rules.entry(frame.label_index).or_default());
+            change(
+                self.alternatives
+                    .entry((frame.label_index, frame.alternative))
+                    .or_default(),
+            );
+        }
+    }
+}
+
+impl Observer<'_> for Profile {
+    fn event(&mut self, event: &Event, _depth: usize, choices: &[ChoiceState]) {
+        // the time since the previous step was spent in the rule the parser was in
+        let now = Instant::now();
+        let elapsed = now - self.last;
+        self.last = now;
+        if !self.stack.is_empty() {
+            self.total += elapsed;
+            self.count(|counters| counters.time += elapsed);
+        }
+
+        // the choices popped since the previous step are forgotten
+        self.parents.truncate(choices.len());
+
+        match *event {
+            Event::Enter { label_index, .. } => {
+                // the reference was just pushed on the choice stack
+                let choice = choices.len() - 1;
+                self.parents
+                    .push(self.stack.last().map_or(0, |frame| frame.choice));
+                self.stack.push(Frame {
+                    label_index,
+                    alternative: 0,
+                    choice,
+                });
+                self.rules.entry(label_index).or_default().entries += 1;
+            }
+            Event::Attempt {
+                label_index,
+                alternative,
+                ..
+            } => {
+                if let Some(frame) = self.stack.last_mut() {
+                    frame.alternative = alternative;
+                }
+                self.alternatives
+                    .entry((label_index, alternative))
+                    .or_default()
+                    .entries += 1;
+            }
+            Event::Match { element, .. } => {
+                // ε is matched without looking at the input
+                if !matches!(element, Element::Empty) {
+                    self.count(|counters| counters.comparisons += 1);
+                }
+            }
+            Event::Mismatch { .. } => self.count(|counters| counters.comparisons += 1),
+            Event::Exit { .. } => {
+                self.count(|counters| counters.matches += 1);
+                self.stack.pop();
+            }
+            // the backtrack that follows is counted for the failing rule, which is still the current one
+            Event::Fail { .. } => {}
+            Event::Backtrack { .. } => {
+                self.count(|counters| counters.backtracks += 1);
+                // the parser goes back to the last choice, which can be in a rule it already left, so
+                // the rules it is in are found again by following the parents of the last choice, up to
+                // the first one the parser is still in
+                let last = choices.len() - 1;
+                let mut choice = last;
+                let mut frames = Vec::new();
+                loop {
+                    let depth = choices[choice].depth;
+                    if self.stack.get(depth).map(|frame| frame.choice) == Some(choice) {
+                        self.stack.truncate(depth + 1);
+                        break;
+                    }
+                    frames.push(Frame {
+                        label_index: choices[choice].destination_label_index,
+                        alternative: choices[choice].destination_alternation_index,
+                        choice,
+                    });
+                    if choice == 0 {
+                        self.stack.clear();
+                        break;
+                    }
+                    choice = self.parents[choice];
+                }
+                self.stack.extend(frames.into_iter().rev());
+                // the last choice already points at the next alternative, which may not exist, so
+                // the parser stays on the failed one until it tries another
+                if let Some(frame) = self.stack.last_mut() {
+                    frame.alternative = choices[last].destination_alternation_index - 1;
+                }
+            }
+        }
+    }
+}
+
+impl Grammar {
+    // parses the input, counting what each rule and alternative cost
+    pub fn parse_profiled(&self, input: &str) -> (Result<Vec<ChoiceState>, Error>, Profile) {
+        let mut profile = Profile::default();
+        let result = self.parse_observed(input, &mut profile);
+        (result, profile)
+    }
+}
+
+// a rule of the profile, with its alternatives
+type RuleCounters<'a> = (usize, &'a Counters, Vec<(usize, &'a Counters)>);
+
+// the rules of the profile, the slowest first, with their alternatives in order
+fn sorted(profile: &Profile) -> Vec<RuleCounters<'_>> {
+    let mut rules: Vec<RuleCounters> = profile
+        .rules
+        .iter()
+        .map(|(&label_index, counters)| {
+            let mut alternatives: Vec<(usize, &Counters)> = profile
+                .alternatives
+                .iter()
+                .filter(|((alternative_label_index, _), _)| *alternative_label_index == label_index)
+                .map(|(&(_, alternative), counters)| (alternative, counters))
+                .collect();
+            alternatives.sort_by_key(|(alternative, _)| *alternative);
+            (label_index, counters, alternatives)
+        })
+        .collect();
+    rules.sort_by(|(a, a_counters, _), (b, b_counters, _)| {
+        b_counters
+            .time
+            .cmp(&a_counters.time)
+            .then(b_counters.entries.cmp(&a_counters.entries))
+            .then(a.cmp(b))
+    });
+    rules
+}
+
+// writes a table with a line per rule, the slowest first, each followed by its alternatives
+pub fn write_profile(
+    output: &mut dyn Write,
+    grammar: &Grammar,
+    profile: &Profile,
+) -> std::fmt::Result {
+    let rules = sorted(profile);
+    let mut lines = vec![[
+        "rule".to_string(),
+        "entries".to_string(),
+        "matches".to_string(),
+        "comparisons".to_string(),
+        "backtracks".to_string(),
+        "time".to_string(),
+        "%".to_string(),
+    ]];
+    let total = profile.total.as_secs_f64();
+    let line = |name: String, counters: &Counters| {
+        let share = if total > 0.0 {
+            counters.time.as_secs_f64() / total * 100.0
+        } else {
+            0.0
+        };
+        [
+            name,
+            counters.entries.to_string(),
+            counters.matches.to_string(),
+            counters.comparisons.to_string(),
+            counters.backtracks.to_string(),
+            format!("{:.3}ms", counters.time.as_secs_f64() * 1000.0),
+            format!("{share:.1}"),
+        ]
+    };
+    for (label_index, counters, alternatives) in rules.iter() {
+        lines.push(line(
+            format!("<{}>", grammar.references[label_index]),
+            counters,
+        ));
+        for (alternative, counters) in alternatives.iter() {
+            lines.push(line(format!("  alternative {}", alternative + 1), counters));
+        }
+    }
+
+    let mut widths = [0; 7];
+    for line in lines.iter() {
+        for (width, cell) in widths.iter_mut().zip(line.iter()) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    for line in lines.iter() {
+        // the names are aligned on the left, the numbers on the right
+        write!(output, "{:<width$}", line[0], width = widths[0])?;
+        for (cell, width) in line.iter().zip(widths.iter()).skip(1) {
+            write!(output, "  {cell:>width$}")?;
+        }
+        writeln!(output)?;
+    }
+    writeln!(
+        output,
+        "total {:.3}ms",
+        profile.total.as_secs_f64() * 1000.0
+    )
+}
+
+// writes the profile as a single JSON object, with the rules in the same order as the table
+pub fn write_profile_json(
+    output: &mut dyn Write,
+    grammar: &Grammar,
+    profile: &Profile,
+) -> std::fmt::Result {
+    let counters_json = |output: &mut dyn Write, counters: &Counters| {
+        write!(
+            output,
+            "\"entries\":{},\"matches\":{},\"comparisons\":{},\"backtracks\":{},\"milliseconds\":{:.3}",
+            counters.entries,
+            counters.matches,
+            counters.comparisons,
+            counters.backtracks,
+            counters.time.as_secs_f64() * 1000.0
+        )
+    };
+    write!(output, "{{\"rules\":[")?;
+    for (i, (label_index, counters, alternatives)) in sorted(profile).iter().enumerate() {
+        if i != 0 {
+            write!(output, ",")?;
+        }
+        write!(output, "{{\"rule\":")?;
+        write_string(output, &grammar.references[label_index])?;
+        write!(output, ",")?;
+        counters_json(output, counters)?;
+        write!(output, ",\"alternatives\":[")?;
+        for (j, (alternative, counters)) in alternatives.iter().enumerate() {
+            if j != 0 {
+                write!(output, ",")?;
+            }
+            // counted from 1, like in the table
+            write!(output, "{{\"alternative\":{},", alternative + 1)?;
+            counters_json(output, counters)?;
+            write!(output, "}}")?;
+        }
+        write!(output, "]}}")?;
+    }
+    writeln!(
+        output,
+        "],\"milliseconds\":{:.3}}}",
+        profile.total.as_secs_f64() * 1000.0
+    )
+}
@@ -1,7 +1,7 @@
 use super::element::{parse_element, Element};
 use super::symbols::parse_spacings;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Sequence {
     pub elements: Vec<Element>,
 }
@@ -23,8 +23,43 @@ impl super::format::Format for Sequence {
     }
 }
 
+impl Sequence {
+    pub fn is_nullable(&self, nullable: &std::collections::HashSet<usize>) -> bool {
+        self.elements.iter().all(|element| match element {
+            Element::Empty => true,
+            Element::Reference(label_index) => nullable.contains(label_index),
+            Element::Range { .. } | Element::Literal(_) => false,
+        })
+    }
+
+    pub fn from_elements(elements: Vec<Element>) -> Sequence {
+        // empty elements are dropped, unless nothing else is left
+        let elements: Vec<Element> = elements
+            .into_iter()
+            .filter(|element| !matches!(element, Element::Empty))
+            .collect();
+        if elements.is_empty() {
+            Sequence {
+                elements: vec![Element::Empty],
+            }
+        } else {
+            Sequence { elements }
+        }
+    }
+
+    pub fn concat(&self, other: &Sequence) -> Sequence {
+        Sequence::from_elements(
+            self.elements
+                .iter()
+                .chain(other.elements.iter())
+                .cloned()
+                .collect(),
+        )
+    }
+}
+
 pub fn parse_sequence(
-    chars: &Vec<char>,
+    chars: &[char],
     mut index: usize,
     labels: &mut std::collections::HashMap<usize, String>,
     labels_reverse: &mut std::collections::HashMap<String, usize>,
@@ -37,7 +72,7 @@ pub fn parse_sequence(
         elements.push(element);
     }
     if elements.is_empty() {
-        return Err(());
+        Err(())
     } else {
         Ok((parse_spacings(chars, index), Sequence { elements }))
     }
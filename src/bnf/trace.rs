@@ -0,0 +1,217 @@
+use super::format::Format;
+use super::grammar::{ChoiceState, Error, Event, Grammar, Observer};
+use crate::json::write_string;
+use std::fmt::Write;
+use std::io;
+
+// how the steps of a trace are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceFormat {
+    // one step per line, indented by the depth of its rule
+    Text,
+    // one JSON object per line and per step
+    Json,
+}
+
+// writes every step of a parse to the output as it happens, so a long parse doesn't pile them up
+pub struct Trace<'g, W> {
+    grammar: &'g Grammar,
+    output: W,
+    format: TraceFormat,
+    // the step being written, kept to reuse its allocation
+    line: String,
+    // the first error of the output, after which the steps aren't written anymore
+    error: Option<io::Error>,
+}
+
+impl<'g, W: io::Write> Trace<'g, W> {
+    pub fn new(grammar: &'g Grammar, output: W, format: TraceFormat) -> Self {
+        Trace {
+            grammar,
+            output,
+            format,
+            line: String::new(),
+            error: None,
+        }
+    }
+
+    // flushes the output and gives it back, or the first error writing to it
+    pub fn finish(mut self) -> io::Result<W> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        self.output.flush()?;
+        Ok(self.output)
+    }
+}
+
+impl<'a, W: io::Write> Observer<'a> for Trace<'_, W> {
+    fn event(&mut self, event: &Event<'a>, depth: usize, _choices: &[ChoiceState]) {
+        if self.error.is_some() {
+            return;
+        }
+        self.line.clear();
+        match self.format {
+            TraceFormat::Text => write!(self.line, "{}", "  ".repeat(depth - 1))
+                .and_then(|_| write_step(&mut self.line, self.grammar, event))
+                .and_then(|_| writeln!(self.line)),
+            TraceFormat::Json => write_step_json(&mut self.line, self.grammar, depth, event),
+        }
+        .expect("Writing to a String should not fail.");
+        if let Err(error) = self.output.write_all(self.line.as_bytes()) {
+            self.error = Some(error);
+        }
+    }
+}
+
+impl Grammar {
+    // parses the input, writing every step of the parser to the output as it happens
+    pub fn parse_traced<W: io::Write>(
+        &self,
+        input: &str,
+        output: W,
+        format: TraceFormat,
+    ) -> (Result<Vec<ChoiceState>, Error>, io::Result<W>) {
+        let mut trace = Trace::new(self, output, format);
+        let result = self.parse_observed(input, &mut trace);
+        (result, trace.finish())
+    }
+}
+
+// writes what the parser did in a step, on a single line without its end, the alternatives being
+// counted from 1 like in the derivation trees
+pub fn write_step(output: &mut dyn Write, grammar: &Grammar, event: &Event) -> std::fmt::Result {
+    match *event {
+        Event::Enter {
+            label_index,
+            input_index,
+        } => write!(
+            output,
+            "enter <{}> at {input_index}",
+            grammar.references[&label_index]
+        ),
+        Event::Attempt {
+            label_index,
+            alternative,
+            input_index,
+        } => write!(
+            output,
+            "try <{}> alternative {} at {input_index}",
+            grammar.references[&label_index],
+            alternative + 1
+        ),
+        Event::Match {
+            element,
+            start,
+            end,
+        } => {
+            write!(output, "match ")?;
+            element.format(output, grammar)?;
+            write!(output, " at {start}..{end}")
+        }
+        Event::Mismatch {
+            element,
+            input_index,
+        } => {
+            write!(output, "mismatch ")?;
+            element.format(output, grammar)?;
+            write!(output, " at {input_index}")
+        }
+        Event::Exit {
+            label_index,
+            alternative,
+            start,
+            end,
+        } => write!(
+            output,
+            "exit <{}> alternative {} at {start}..{end}",
+            grammar.references[&label_index],
+            alternative + 1
+        ),
+        Event::Fail {
+            label_index,
+            input_index,
+        } => write!(
+            output,
+            "fail <{}> at {input_index}",
+            grammar.references[&label_index]
+        ),
+        Event::Backtrack {
+            label_index,
+            input_index,
+        } => write!(
+            output,
+            "backtrack to <{}> at {input_index}",
+            grammar.references[&label_index]
+        ),
+    }
+}
+
+// writes a step as a JSON object on its own line, the alternatives being counted from 1 too
+pub fn write_step_json(
+    output: &mut dyn Write,
+    grammar: &Grammar,
+    depth: usize,
+    event: &Event,
+) -> std::fmt::Result {
+    let (name, label_index) = match *event {
+        Event::Enter { label_index, .. } => ("enter", Some(label_index)),
+        Event::Attempt { label_index, .. } => ("attempt", Some(label_index)),
+        Event::Match { .. } => ("match", None),
+        Event::Mismatch { .. } => ("mismatch", None),
+        Event::Exit { label_index, .. } => ("exit", Some(label_index)),
+        Event::Fail { label_index, .. } => ("fail", Some(label_index)),
+        Event::Backtrack { label_index, .. } => ("backtrack", Some(label_index)),
+    };
+    write!(output, "{{\"event\":\"{name}\",\"depth\":{depth}")?;
+    if let Some(label_index) = label_index {
+        write!(output, ",\"rule\":")?;
+        write_string(output, &grammar.references[&label_index])?;
+    }
+    match *event {
+        Event::Enter { input_index, .. }
+        | Event::Fail { input_index, .. }
+        | Event::Backtrack { input_index, .. } => write!(output, ",\"offset\":{input_index}")?,
+        Event::Attempt {
+            alternative,
+            input_index,
+            ..
+        } => write!(
+            output,
+            ",\"alternative\":{},\"offset\":{input_index}",
+            alternative + 1
+        )?,
+        Event::Match {
+            element,
+            start,
+            end,
+        } => {
+            let mut text = String::new();
+            element.format(&mut text, grammar)?;
+            write!(output, ",\"element\":")?;
+            write_string(output, &text)?;
+            write!(output, ",\"start\":{start},\"end\":{end}")?;
+        }
+        Event::Mismatch {
+            element,
+            input_index,
+        } => {
+            let mut text = String::new();
+            element.format(&mut text, grammar)?;
+            write!(output, ",\"element\":")?;
+            write_string(output, &text)?;
+            write!(output, ",\"offset\":{input_index}")?;
+        }
+        Event::Exit {
+            alternative,
+            start,
+            end,
+            ..
+        } => write!(
+            output,
+            ",\"alternative\":{},\"start\":{start},\"end\":{end}",
+            alternative + 1
+        )?,
+    }
+    writeln!(output, "}}")
+}
@@ -0,0 +1,341 @@
+use super::element::Element;
+use super::grammar::{ChoiceState, Error, Grammar};
+use crate::json::write_string;
+use crate::xml::{write_content, write_escaped};
+use std::fmt::Write;
+
+// the derivation of a parsed input, the positions being indices in its characters
+#[derive(Debug, Clone)]
+pub enum Tree {
+    // a rule, with the alternative it matched with, counted from 0 here but from 1 by the writers
+    Rule {
+        label_index: usize,
+        alternative: usize,
+        start: usize,
+        end: usize,
+        children: Vec<Tree>,
+    },
+    // a literal or a range, ε leaving nothing in the tree
+    Terminal {
+        start: usize,
+        end: usize,
+    },
+}
+
+impl Tree {
+    pub fn start(&self) -> usize {
+        match self {
+            Tree::Rule { start, .. } | Tree::Terminal { start, .. } => *start,
+        }
+    }
+
+    pub fn end(&self) -> usize {
+        match self {
+            Tree::Rule { end, .. } | Tree::Terminal { end, .. } => *end,
+        }
+    }
+}
+
+// a rule of the tree being built, which is complete once all the elements of its alternative are
+struct Frame {
+    label_index: usize,
+    alternative: usize,
+    start: usize,
+    element: usize,
+    index: usize,
+    children: Vec<Tree>,
+}
+
+impl Grammar {
+    pub fn parse_tree(&self, input: &str) -> Result<Tree, Error> {
+        let choices = self.parse(input)?;
+        Ok(self.tree(&choices))
+    }
+
+    // rebuilds the derivation from the choice stack of a successful parse: it holds every reference
+    // of the derivation in the order the parser entered them, with the alternative that matched
+    pub fn tree(&self, choices: &[ChoiceState]) -> Tree {
+        let root = &choices[0];
+        let mut next_choice = 1;
+        // the rules are followed with an explicit stack, like in the parser, so deep derivations fit
+        let mut stack = vec![Frame {
+            label_index: root.destination_label_index,
+            alternative: root.destination_alternation_index,
+            start: 0,
+            element: 0,
+            index: 0,
+            children: Vec::new(),
+        }];
+        loop {
+            let frame = stack.last_mut().expect("Frame stack should not be empty.");
+            let sequence = &self.maps[&frame.label_index].sequences[frame.alternative];
+            let Some(element) = sequence.elements.get(frame.element) else {
+                let frame = stack.pop().expect("Frame stack should not be empty.");
+                let rule = Tree::Rule {
+                    label_index: frame.label_index,
+                    alternative: frame.alternative,
+                    start: frame.start,
+                    end: frame.index,
+                    children: frame.children,
+                };
+                match stack.last_mut() {
+                    Some(parent) => {
+                        parent.index = rule.end();
+                        parent.children.push(rule);
+                        parent.element += 1;
+                        continue;
+                    }
+                    None => return rule,
+                }
+            };
+            let length = match element {
+                Element::Empty => 0,
+                Element::Literal(literal) => literal.chars().count(),
+                Element::Range { .. } => 1,
+                Element::Reference(label_index) => {
+                    let choice = &choices[next_choice];
+                    next_choice += 1;
+                    let index = frame.index;
+                    stack.push(Frame {
+                        label_index: *label_index,
+                        alternative: choice.destination_alternation_index,
+                        start: index,
+                        element: 0,
+                        index,
+                        children: Vec::new(),
+                    });
+                    continue;
+                }
+            };
+            if !matches!(element, Element::Empty) {
+                frame.children.push(Tree::Terminal {
+                    start: frame.index,
+                    end: frame.index + length,
+                });
+            }
+            frame.index += length;
+            frame.element += 1;
+        }
+    }
+}
+
+// writes the tree with one rule or terminal per line, indented by depth, with the text it matched
+pub fn write_tree(
+    output: &mut dyn Write,
+    grammar: &Grammar,
+    tree: &Tree,
+    chars: &[char],
+) -> std::fmt::Result {
+    let mut stack = vec![(tree, 0)];
+    while let Some((tree, depth)) = stack.pop() {
+        let text: String = chars[tree.start()..tree.end()].iter().collect();
+        write!(output, "{}", "  ".repeat(depth))?;
+        match tree {
+            Tree::Rule {
+                label_index,
+                alternative,
+                start,
+                end,
+                children,
+            } => {
+                writeln!(
+                    output,
+                    "<{}> alternative {}, {start}..{end} {text:?}",
+                    grammar.references[label_index],
+                    alternative + 1
+                )?;
+                stack.extend(children.iter().rev().map(|child| (child, depth + 1)));
+            }
+            Tree::Terminal { .. } => writeln!(output, "{text:?}")?,
+        }
+    }
+    Ok(())
+}
+
+// a step of the writers: a tree to write, or the end of a rule whose children were written
+enum Step<'a> {
+    Tree(&'a Tree, usize),
+    End(usize),
+}
+
+// writes the tree as a single line of JSON, the rules like
+// {"type":"rule","rule":"name","alternative":1,"start":0,"end":3,"children":[...]} and the terminals
+// like {"type":"terminal","start":0,"end":1,"text":"a"}, the alternatives being counted from 1 and
+// the positions being indices in the characters of the input
+pub fn write_tree_json(
+    output: &mut dyn Write,
+    grammar: &Grammar,
+    tree: &Tree,
+    chars: &[char],
+) -> std::fmt::Result {
+    // the second value of the steps tells whether a sibling was written before
+    let mut stack = vec![Step::Tree(tree, 0)];
+    while let Some(step) = stack.pop() {
+        match step {
+            Step::Tree(tree, index) => {
+                if index != 0 {
+                    write!(output, ",")?;
+                }
+                match tree {
+                    Tree::Rule {
+                        label_index,
+                        alternative,
+                        start,
+                        end,
+                        children,
+                    } => {
+                        write!(output, "{{\"type\":\"rule\",\"rule\":")?;
+                        write_string(output, &grammar.references[label_index])?;
+                        write!(
+                            output,
+                            ",\"alternative\":{},\"start\":{start},\"end\":{end},\"children\":[",
+                            alternative + 1
+                        )?;
+                        stack.push(Step::End(0));
+                        stack.extend(
+                            children
+                                .iter()
+                                .enumerate()
+                                .rev()
+                                .map(|(index, child)| Step::Tree(child, index)),
+                        );
+                    }
+                    Tree::Terminal { start, end } => {
+                        let text: String = chars[*start..*end].iter().collect();
+                        write!(
+                            output,
+                            "{{\"type\":\"terminal\",\"start\":{start},\"end\":{end},\"text\":"
+                        )?;
+                        write_string(output, &text)?;
+                        write!(output, "}}")?;
+                    }
+                }
+            }
+            Step::End(_) => write!(output, "]}}")?,
+        }
+    }
+    writeln!(output)
+}
+
+// writes the tree as a single S-expression, the rules like (rule "name" (alternative 1) (span 0 3) ...)
+// and the terminals like (terminal (span 0 1) "a"), with the same numbers and positions as the JSON
+pub fn write_tree_sexp(
+    output: &mut dyn Write,
+    grammar: &Grammar,
+    tree: &Tree,
+    chars: &[char],
+) -> std::fmt::Result {
+    let mut stack = vec![Step::Tree(tree, 0)];
+    while let Some(step) = stack.pop() {
+        match step {
+            Step::Tree(tree, index) => {
+                if index != 0 {
+                    write!(output, " ")?;
+                }
+                match tree {
+                    Tree::Rule {
+                        label_index,
+                        alternative,
+                        start,
+                        end,
+                        children,
+                    } => {
+                        write!(output, "(rule ")?;
+                        write_sexp_string(output, &grammar.references[label_index])?;
+                        write!(
+                            output,
+                            " (alternative {}) (span {start} {end})",
+                            alternative + 1
+                        )?;
+                        stack.push(Step::End(0));
+                        // the children all follow the span
+                        stack.extend(children.iter().rev().map(|child| Step::Tree(child, 1)));
+                    }
+                    Tree::Terminal { start, end } => {
+                        let text: String = chars[*start..*end].iter().collect();
+                        write!(output, "(terminal (span {start} {end}) ")?;
+                        write_sexp_string(output, &text)?;
+                        write!(output, ")")?;
+                    }
+                }
+            }
+            Step::End(_) => write!(output, ")")?,
+        }
+    }
+    writeln!(output)
+}
+
+// writes the string between quotes, with the escapes of Scheme
+fn write_sexp_string(output: &mut dyn Write, string: &str) -> std::fmt::Result {
+    write!(output, "\"")?;
+    for c in string.chars() {
+        match c {
+            '"' => write!(output, "\\\"")?,
+            '\\' => write!(output, "\\\\")?,
+            '\n' => write!(output, "\\n")?,
+            '\r' => write!(output, "\\r")?,
+            '\t' => write!(output, "\\t")?,
+            c => write!(output, "{c}")?,
+        }
+    }
+    write!(output, "\"")
+}
+
+// writes the tree as an XML document with an element per line, the rules like
+// <rule name="name" alternative="1" start="0" end="3"> and the terminals like
+// <terminal start="0" end="1">a</terminal>, with the same numbers and positions as the JSON
+pub fn write_tree_xml(
+    output: &mut dyn Write,
+    grammar: &Grammar,
+    tree: &Tree,
+    chars: &[char],
+) -> std::fmt::Result {
+    writeln!(output, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    // the second value of the steps is the depth
+    let mut stack = vec![Step::Tree(tree, 0)];
+    while let Some(step) = stack.pop() {
+        match step {
+            Step::Tree(tree, depth) => {
+                write!(output, "{}", "  ".repeat(depth))?;
+                match tree {
+                    Tree::Rule {
+                        label_index,
+                        alternative,
+                        start,
+                        end,
+                        children,
+                    } => {
+                        write!(output, "<rule name=\"")?;
+                        write_escaped(output, &grammar.references[label_index])?;
+                        write!(
+                            output,
+                            "\" alternative=\"{}\" start=\"{start}\" end=\"{end}\"",
+                            alternative + 1
+                        )?;
+                        // a rule that matched ε has no children
+                        if children.is_empty() {
+                            writeln!(output, "/>")?;
+                            continue;
+                        }
+                        writeln!(output, ">")?;
+                        stack.push(Step::End(depth));
+                        stack.extend(
+                            children
+                                .iter()
+                                .rev()
+                                .map(|child| Step::Tree(child, depth + 1)),
+                        );
+                    }
+                    Tree::Terminal { start, end } => {
+                        let text: String = chars[*start..*end].iter().collect();
+                        write!(output, "<terminal start=\"{start}\" end=\"{end}\">")?;
+                        write_content(output, &text)?;
+                        writeln!(output, "</terminal>")?;
+                    }
+                }
+            }
+            Step::End(depth) => writeln!(output, "{}</rule>", "  ".repeat(depth))?,
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,186 @@
+use super::{read_grammar, value, Format, Output, Verbosity, ACCEPTED, FAILED, REJECTED};
+use backus_naur_parser::analysis::lint::{
+    lint, ConfigError, Diagnostic, Lint, Severities, Severity,
+};
+use backus_naur_parser::bnf::grammar::parse_grammar_lenient;
+use backus_naur_parser::json::write_string;
+use std::process::ExitCode;
+
+// bnf check <grammar>...
+pub fn run(arguments: &[String]) -> Result<ExitCode, String> {
+    let mut grammar_paths = Vec::new();
+    let mut config_path = None;
+    // the flags win over the config file, whatever their order
+    let mut overrides = Vec::new();
+    let mut output = Output::default();
+    let mut arguments = arguments.iter();
+    while let Some(argument) = arguments.next() {
+        if output.parse_option(argument, &mut arguments)? {
+            continue;
+        }
+        let severity = match argument.as_str() {
+            "-A" | "--allow" => Severity::Allow,
+            "-W" | "--warn" => Severity::Warn,
+            "-D" | "--deny" => Severity::Deny,
+            "--config" => {
+                config_path = Some(value(argument, &mut arguments)?);
+                continue;
+            }
+            "-g" | "--grammar" => {
+                grammar_paths.push(value(argument, &mut arguments)?);
+                continue;
+            }
+            path if !path.starts_with('-') => {
+                grammar_paths.push(path.to_string());
+                continue;
+            }
+            option => return Err(format!("unknown option `{option}`")),
+        };
+        let name = value(argument, &mut arguments)?;
+        let lint = Lint::from_name(&name).ok_or_else(|| format!("unknown lint `{name}`"))?;
+        overrides.push((lint, severity));
+    }
+    if grammar_paths.is_empty() {
+        return Err("missing the grammar to check".to_string());
+    }
+
+    let mut severities = Severities::default();
+    if let Some(config_path) = config_path {
+        let config = match std::fs::read_to_string(&config_path) {
+            Ok(config) => config,
+            Err(error) => {
+                eprintln!("error: {config_path}: couldn't read the file: {error}");
+                return Ok(ExitCode::from(FAILED));
+            }
+        };
+        if let Err(error) = severities.read_config(&config) {
+            let message = match error {
+                ConfigError::Syntax { line } => format!("{line}: expected `lint = severity`"),
+                ConfigError::UnknownLint { line, name } => format!("{line}: unknown lint `{name}`"),
+                ConfigError::UnknownSeverity { line, name } => {
+                    format!("{line}: unknown severity `{name}`, expected allow, warn or deny")
+                }
+            };
+            eprintln!("error: {config_path}:{message}");
+            return Ok(ExitCode::from(FAILED));
+        }
+    }
+    for (lint, severity) in overrides {
+        severities.set(lint, severity);
+    }
+
+    let mut code = ACCEPTED;
+    for path in grammar_paths.iter() {
+        let Some((grammar, source)) = read_grammar(path, &output, parse_grammar_lenient) else {
+            code = FAILED;
+            continue;
+        };
+        let diagnostics = lint(&grammar);
+        let count = |severity: Severity| {
+            diagnostics
+                .iter()
+                .filter(|diagnostic| severities.get(diagnostic.lint) == severity)
+                .count()
+        };
+        let (errors, warnings) = (count(Severity::Deny), count(Severity::Warn));
+        if errors > 0 && code == ACCEPTED {
+            code = REJECTED;
+        }
+        if output.verbosity == Verbosity::Quiet {
+            continue;
+        }
+
+        // the allowed lints are only shown when asked for
+        let shown: Vec<(&Diagnostic, Severity)> = diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic, severities.get(diagnostic.lint)))
+            .filter(|(_, severity)| {
+                *severity != Severity::Allow || output.verbosity == Verbosity::Verbose
+            })
+            .collect();
+        match output.format {
+            Format::Text => {
+                for (diagnostic, severity) in shown {
+                    let (line, column) = locate(&source, diagnostic);
+                    let kind = match severity {
+                        Severity::Allow => "allowed",
+                        Severity::Warn => "warning",
+                        Severity::Deny => "error",
+                    };
+                    println!(
+                        "{kind}[{}]: {path}:{line}:{column}: {diagnostic}",
+                        diagnostic.lint.name()
+                    );
+                }
+                if errors + warnings > 0 || output.verbosity == Verbosity::Verbose {
+                    println!(
+                        "{path}: {errors} error{}, {warnings} warning{}",
+                        plural(errors),
+                        plural(warnings)
+                    );
+                }
+            }
+            Format::Json => {
+                let mut json = String::from("{\"grammar\":");
+                write_string(&mut json, path).expect("Writing to a String should not fail.");
+                json.push_str(&format!(
+                    ",\"errors\":{errors},\"warnings\":{warnings},\"diagnostics\":["
+                ));
+                for (i, (diagnostic, severity)) in shown.into_iter().enumerate() {
+                    if i != 0 {
+                        json.push(',');
+                    }
+                    let (line, column) = locate(&source, diagnostic);
+                    json.push_str(&format!(
+                        "{{\"lint\":\"{}\",\"severity\":\"{}\",\"rule\":",
+                        diagnostic.lint.name(),
+                        severity.name()
+                    ));
+                    write_string(&mut json, &diagnostic.rule)
+                        .expect("Writing to a String should not fail.");
+                    // counted from 1, like in the messages
+                    if let Some(alternative) = diagnostic.alternative {
+                        json.push_str(&format!(",\"alternative\":{}", alternative + 1));
+                    }
+                    json.push_str(&format!(
+                        ",\"line\":{line},\"column\":{column},\"message\":"
+                    ));
+                    write_string(&mut json, &diagnostic.to_string())
+                        .expect("Writing to a String should not fail.");
+                    json.push('}');
+                }
+                println!("{json}]}}");
+            }
+        }
+    }
+    Ok(ExitCode::from(code))
+}
+
+// where the diagnostic points in the grammar: the first use of an undefined rule, the definition
+// of the rule otherwise
+fn locate(source: &str, diagnostic: &Diagnostic) -> (usize, usize) {
+    let reference = format!("<{}>", diagnostic.rule);
+    for (i, line) in source.lines().enumerate() {
+        let found = if diagnostic.lint == Lint::UndefinedRule {
+            line.find(&reference)
+        } else {
+            let start = line.len() - line.trim_start().len();
+            line[start..]
+                .strip_prefix(&reference)
+                .filter(|rest| rest.trim_start().starts_with("::="))
+                .map(|_| start)
+        };
+        if let Some(position) = found {
+            return (i + 1, line[..position].chars().count() + 1);
+        }
+    }
+    (1, 1)
+}
+
+fn plural(count: usize) -> &'static str {
+    if count == 1 {
+        ""
+    } else {
+        "s"
+    }
+}
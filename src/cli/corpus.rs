@@ -0,0 +1,238 @@
+use super::{load_grammar, Format, Output, Verbosity, ACCEPTED, FAILED, REJECTED};
+use backus_naur_parser::bnf::grammar::Error;
+use backus_naur_parser::json::write_string;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::{Duration, SystemTime};
+
+// what happened to an input of the corpus, an input passing when the grammar accepts it
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Status {
+    Accepted,
+    Rejected,
+    StackOverflow,
+    Unreadable(String),
+}
+
+impl Status {
+    fn passed(&self) -> bool {
+        *self == Status::Accepted
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Status::Accepted => "accepted".to_string(),
+            Status::Rejected => "rejected".to_string(),
+            Status::StackOverflow => "rejected: stack overflow".to_string(),
+            Status::Unreadable(error) => format!("couldn't be read: {error}"),
+        }
+    }
+}
+
+// the results of a run, by input path
+type Results = BTreeMap<String, Status>;
+
+// the modification time and size of every watched file, any change triggering a new run
+type Snapshot = Vec<(PathBuf, Option<SystemTime>, Option<u64>)>;
+
+// parses every file of the corpus, then again whenever the grammar or the corpus changes when watching,
+// polling the files every `watch` milliseconds
+pub fn run(grammar_path: &str, inputs: &[String], output: &Output, watch: Option<u64>) -> ExitCode {
+    let Some(interval) = watch else {
+        let Some(results) = run_corpus(grammar_path, inputs, output) else {
+            return ExitCode::from(FAILED);
+        };
+        report(1, &results, None, output);
+        let passed = results.values().all(Status::passed);
+        return ExitCode::from(if passed { ACCEPTED } else { REJECTED });
+    };
+
+    let mut previous: Option<Results> = None;
+    let mut run = 0;
+    let mut snapshot = None;
+    loop {
+        let current = take_snapshot(grammar_path, inputs);
+        if snapshot.as_ref() != Some(&current) {
+            snapshot = Some(current);
+            run += 1;
+            // an invalid grammar is reported by load_grammar, the next run being compared to the last
+            // valid one
+            if let Some(results) = run_corpus(grammar_path, inputs, output) {
+                report(run, &results, previous.as_ref(), output);
+                previous = Some(results);
+            }
+        }
+        std::thread::sleep(Duration::from_millis(interval));
+    }
+}
+
+fn run_corpus(grammar_path: &str, inputs: &[String], output: &Output) -> Option<Results> {
+    let grammar = load_grammar(grammar_path, output)?;
+    let mut results = Results::new();
+    for file in files(inputs) {
+        let status = match std::fs::read_to_string(&file) {
+            Ok(input) => match grammar.parse(&input) {
+                Ok(_) => Status::Accepted,
+                Err(Error::NoMatch) => Status::Rejected,
+                Err(Error::StackOverflow) => Status::StackOverflow,
+                Err(Error::Stopped) => unreachable!("Only the debugger should stop a parse."),
+            },
+            Err(error) => Status::Unreadable(error.to_string()),
+        };
+        results.insert(file.display().to_string(), status);
+    }
+    Some(results)
+}
+
+// the files given, and the files inside the directories given, in order
+fn files(inputs: &[String]) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut pending: Vec<PathBuf> = inputs.iter().rev().map(PathBuf::from).collect();
+    while let Some(path) = pending.pop() {
+        if path.is_dir() {
+            let Ok(entries) = std::fs::read_dir(&path) else {
+                continue;
+            };
+            let mut children: Vec<PathBuf> = entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .collect();
+            children.sort();
+            pending.extend(children.into_iter().rev());
+        } else {
+            files.push(path);
+        }
+    }
+    files
+}
+
+fn take_snapshot(grammar_path: &str, inputs: &[String]) -> Snapshot {
+    std::iter::once(PathBuf::from(grammar_path))
+        .chain(files(inputs))
+        .map(|path| {
+            let metadata = std::fs::metadata(&path).ok();
+            let modified = metadata
+                .as_ref()
+                .and_then(|metadata| metadata.modified().ok());
+            let length = metadata.map(|metadata| metadata.len());
+            (path, modified, length)
+        })
+        .collect()
+}
+
+// the inputs that started or stopped passing, appeared or disappeared since the previous run
+struct Changes<'a> {
+    passing: Vec<&'a str>,
+    failing: Vec<&'a str>,
+    added: Vec<&'a str>,
+    removed: Vec<&'a str>,
+}
+
+fn changes<'a>(results: &'a Results, previous: &'a Results) -> Changes<'a> {
+    let mut changes = Changes {
+        passing: Vec::new(),
+        failing: Vec::new(),
+        added: Vec::new(),
+        removed: Vec::new(),
+    };
+    for (path, status) in results.iter() {
+        match previous.get(path) {
+            None => changes.added.push(path),
+            Some(before) if before.passed() && !status.passed() => changes.failing.push(path),
+            Some(before) if !before.passed() && status.passed() => changes.passing.push(path),
+            Some(_) => {}
+        }
+    }
+    for path in previous.keys() {
+        if !results.contains_key(path) {
+            changes.removed.push(path);
+        }
+    }
+    changes
+}
+
+fn report(run: usize, results: &Results, previous: Option<&Results>, output: &Output) {
+    if output.verbosity == Verbosity::Quiet {
+        return;
+    }
+    let passed = results.values().filter(|status| status.passed()).count();
+    let failed = results.len() - passed;
+    let changes = previous.map(|previous| changes(results, previous));
+    // the first run lists what fails, the next ones what changed
+    let failing: Vec<(&String, &Status)> = results
+        .iter()
+        .filter(|(_, status)| !status.passed())
+        .collect();
+
+    match output.format {
+        Format::Text => {
+            println!("[run {run}] {passed} passed, {failed} failed");
+            match &changes {
+                None => {
+                    for (path, status) in failing.iter() {
+                        println!("  fail {path}: {}", status.describe());
+                    }
+                }
+                Some(changes) => {
+                    for path in changes.passing.iter() {
+                        println!("  now passing {path}");
+                    }
+                    for path in changes.failing.iter() {
+                        println!("  now failing {path}: {}", results[*path].describe());
+                    }
+                    for path in changes.added.iter() {
+                        println!("  new {path}: {}", results[*path].describe());
+                    }
+                    for path in changes.removed.iter() {
+                        println!("  removed {path}");
+                    }
+                    if changes.passing.is_empty()
+                        && changes.failing.is_empty()
+                        && changes.added.is_empty()
+                        && changes.removed.is_empty()
+                    {
+                        println!("  no change");
+                    }
+                }
+            }
+            if output.verbosity == Verbosity::Verbose {
+                for (path, status) in results.iter().filter(|(_, status)| status.passed()) {
+                    println!("  pass {path}: {}", status.describe());
+                }
+                if changes.is_some() {
+                    for (path, status) in failing.iter() {
+                        println!("  fail {path}: {}", status.describe());
+                    }
+                }
+            }
+        }
+        Format::Json => {
+            let list = |paths: &[&str]| {
+                let mut json = String::from("[");
+                for (i, path) in paths.iter().enumerate() {
+                    if i != 0 {
+                        json.push(',');
+                    }
+                    write_string(&mut json, path).expect("Writing to a String should not fail.");
+                }
+                json.push(']');
+                json
+            };
+            let failing: Vec<&str> = failing.iter().map(|(path, _)| path.as_str()).collect();
+            let mut json = format!(
+                "{{\"run\":{run},\"passed\":{passed},\"failed\":{failed},\"failing\":{}",
+                list(&failing)
+            );
+            if let Some(changes) = &changes {
+                json.push_str(&format!(
+                    ",\"now_passing\":{},\"now_failing\":{},\"added\":{},\"removed\":{}",
+                    list(&changes.passing),
+                    list(&changes.failing),
+                    list(&changes.added),
+                    list(&changes.removed)
+                ));
+            }
+            println!("{json}}}");
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use super::{load_grammar, value, Output, ACCEPTED, FAILED, REJECTED};
+use backus_naur_parser::bnf::debugger::{write_choices, Breakpoint, Command, Debugger, Pause};
+use backus_naur_parser::bnf::grammar::{Error, Grammar};
+use backus_naur_parser::bnf::trace::write_step;
+use std::io::{BufRead, IsTerminal, Write};
+use std::process::ExitCode;
+
+const HELP: &str = "the parser is paused at the step printed, type:
+  s, step             goes to the next step
+  n, next             goes to the next step, skipping the reference entered or the ones of the rule
+  o, out              goes to the first step out of the current rule
+  c, continue         goes to the next breakpoint
+  b, break <rule>|<offset>
+                      pauses when the parser goes into the rule, or arrives at the offset
+  d, delete <rule>|<offset>
+                      removes the breakpoint
+  breakpoints         lists the breakpoints
+  bt, stack           prints the choice stack, from the entrypoint to the last reference entered
+  input               prints the input, split where the parser is
+  h, help             prints this message
+  q, quit             stops the parse
+an empty line repeats the last command, and the end of the input continues to the end of the parse
+";
+
+// bnf debug --grammar <file> <input> [--break <rule>|<offset>]...
+pub fn run(arguments: &[String]) -> Result<ExitCode, String> {
+    let mut grammar_path = None;
+    let mut input_path = None;
+    let mut breaks = Vec::new();
+    let mut output = Output::default();
+    let mut arguments = arguments.iter();
+    while let Some(argument) = arguments.next() {
+        if output.parse_option(argument, &mut arguments)? {
+            continue;
+        }
+        match argument.as_str() {
+            "-g" | "--grammar" => grammar_path = Some(value(argument, &mut arguments)?),
+            "-b" | "--break" => breaks.push(value(argument, &mut arguments)?),
+            // the standard input is where the commands come from
+            path if !path.starts_with('-') => {
+                if input_path.replace(path.to_string()).is_some() {
+                    return Err("only one input can be debugged at a time".to_string());
+                }
+            }
+            option => return Err(format!("unknown option `{option}`")),
+        }
+    }
+    let grammar_path = grammar_path.ok_or("missing `--grammar <file>`")?;
+    let input_path = input_path.ok_or("missing the input file")?;
+
+    let Some(grammar) = load_grammar(&grammar_path, &output) else {
+        return Ok(ExitCode::from(FAILED));
+    };
+    let breakpoints = breaks
+        .iter()
+        .map(|argument| breakpoint(&grammar, argument))
+        .collect::<Result<Vec<Breakpoint>, String>>()?;
+    let input = match std::fs::read_to_string(&input_path) {
+        Ok(input) => input,
+        Err(error) => {
+            eprintln!("error: {input_path}: couldn't read the input: {error}");
+            return Ok(ExitCode::from(FAILED));
+        }
+    };
+    let chars: Vec<char> = input.chars().collect();
+
+    // the prompt would only get in the way of piped sessions
+    let interactive = std::io::stdin().is_terminal();
+    let mut lines = std::io::stdin().lock().lines();
+    let mut last_line = String::new();
+    let mut debugger = Debugger::new(breakpoints, |pause: &Pause, breakpoints| {
+        print_pause(&grammar, pause);
+        loop {
+            if interactive {
+                print!("(bnf) ");
+                // a prompt that can't be shown is no reason to stop the session
+                let _ = std::io::stdout().flush();
+            }
+            let Some(Ok(line)) = lines.next() else {
+                return Command::Continue;
+            };
+            let line = if line.trim().is_empty() {
+                last_line.clone()
+            } else {
+                line.trim().to_string()
+            };
+            last_line = line.clone();
+            let (command, argument) = line.split_once(' ').unwrap_or((&line, ""));
+            let argument = argument.trim();
+            match command {
+                "s" | "step" => return Command::StepInto,
+                "n" | "next" => return Command::StepOver,
+                "o" | "out" => return Command::StepOut,
+                "c" | "continue" => return Command::Continue,
+                "q" | "quit" => return Command::Quit,
+                "b" | "break" => match breakpoint(&grammar, argument) {
+                    Ok(breakpoint) if breakpoints.contains(&breakpoint) => {
+                        println!("{argument} already has a breakpoint")
+                    }
+                    Ok(breakpoint) => {
+                        breakpoints.push(breakpoint);
+                        println!("breakpoint on {}", describe(&grammar, &breakpoint));
+                    }
+                    Err(message) => println!("{message}"),
+                },
+                "d" | "delete" => match breakpoint(&grammar, argument) {
+                    Ok(breakpoint) if breakpoints.contains(&breakpoint) => {
+                        breakpoints.retain(|existing| *existing != breakpoint);
+                        println!(
+                            "removed the breakpoint on {}",
+                            describe(&grammar, &breakpoint)
+                        );
+                    }
+                    Ok(_) => println!("{argument} has no breakpoint"),
+                    Err(message) => println!("{message}"),
+                },
+                "breakpoints" => {
+                    if breakpoints.is_empty() {
+                        println!("no breakpoints");
+                    }
+                    for breakpoint in breakpoints.iter() {
+                        println!("{}", describe(&grammar, breakpoint));
+                    }
+                }
+                "bt" | "stack" => {
+                    let mut text = String::new();
+                    write_choices(&mut text, &grammar, pause.choices)
+                        .expect("Writing to a String should not fail.");
+                    print!("{text}");
+                }
+                "input" => {
+                    let position = pause.event.position().min(chars.len());
+                    let before: String = chars[..position].iter().collect();
+                    let after: String = chars[position..].iter().collect();
+                    println!("{before:?} | {after:?}");
+                }
+                "h" | "help" => print!("{HELP}"),
+                command => println!("unknown command {command}, help lists the commands"),
+            }
+        }
+    });
+    let result = grammar.parse_observed(&input, &mut debugger);
+
+    let code = match result {
+        Ok(_) => {
+            println!("accepted");
+            ACCEPTED
+        }
+        Err(error) => {
+            match error {
+                Error::NoMatch => println!("rejected: no match"),
+                Error::StackOverflow => println!("rejected: stack overflow"),
+                Error::Stopped => println!("stopped"),
+            }
+            REJECTED
+        }
+    };
+    Ok(ExitCode::from(code))
+}
+
+// a rule name, with or without its brackets, or an offset in the input
+fn breakpoint(grammar: &Grammar, argument: &str) -> Result<Breakpoint, String> {
+    if let Ok(offset) = argument.parse() {
+        return Ok(Breakpoint::Offset(offset));
+    }
+    let label = argument.trim_start_matches('<').trim_end_matches('>');
+    match grammar.label_index(label) {
+        Some(label_index) if grammar.maps.contains_key(&label_index) => {
+            Ok(Breakpoint::Rule(label_index))
+        }
+        _ => Err(format!("<{label}> doesn't exist")),
+    }
+}
+
+fn describe(grammar: &Grammar, breakpoint: &Breakpoint) -> String {
+    match breakpoint {
+        Breakpoint::Rule(label_index) => format!("<{}>", grammar.references[label_index]),
+        Breakpoint::Offset(offset) => format!("offset {offset}"),
+    }
+}
+
+fn print_pause(grammar: &Grammar, pause: &Pause) {
+    let mut text = String::new();
+    if let Some(breakpoint) = &pause.breakpoint {
+        text.push_str(&format!(
+            "breakpoint on {}\n",
+            describe(grammar, breakpoint)
+        ));
+    }
+    write_step(&mut text, grammar, pause.event).expect("Writing to a String should not fail.");
+    println!("{text} (depth {})", pause.depth);
+}
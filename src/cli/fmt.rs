@@ -0,0 +1,113 @@
+use super::{value, Format, Output, Verbosity, ACCEPTED, FAILED, REJECTED};
+use backus_naur_parser::bnf::grammar::parse_grammar_checked;
+use backus_naur_parser::bnf::pretty::{write_pretty, Options};
+use backus_naur_parser::json::write_string;
+use std::io::Read;
+use std::process::ExitCode;
+
+// bnf fmt [--check] [--width <n>] [<grammar>...]
+pub fn run(arguments: &[String]) -> Result<ExitCode, String> {
+    let mut paths = Vec::new();
+    let mut check = false;
+    let mut options = Options::default();
+    let mut output = Output::default();
+    let mut arguments = arguments.iter();
+    while let Some(argument) = arguments.next() {
+        if output.parse_option(argument, &mut arguments)? {
+            continue;
+        }
+        match argument.as_str() {
+            "--check" => check = true,
+            "--width" => {
+                let width = value(argument, &mut arguments)?;
+                options.width = width
+                    .parse()
+                    .map_err(|_| format!("invalid width `{width}`"))?;
+            }
+            path if !path.starts_with('-') || path == "-" => paths.push(path.to_string()),
+            option => return Err(format!("unknown option `{option}`")),
+        }
+    }
+    // without files, the standard input is formatted to the standard output
+    let standard = paths.is_empty();
+    if standard {
+        paths.push("-".to_string());
+    }
+
+    let mut code = ACCEPTED;
+    for path in paths.iter() {
+        let source = if path == "-" {
+            let mut source = String::new();
+            std::io::stdin().read_to_string(&mut source).map(|_| source)
+        } else {
+            std::fs::read_to_string(path)
+        };
+        let source = match source {
+            Ok(source) => source,
+            Err(error) => {
+                report(path, &format!("couldn't read the file: {error}"), &output);
+                code = FAILED;
+                continue;
+            }
+        };
+        let chars: Vec<char> = source.chars().collect();
+        let grammar = match parse_grammar_checked(&chars, 0) {
+            Ok(grammar) => grammar,
+            Err(error) => {
+                let (line, column) = error.position(&chars);
+                report(path, &format!("{line}:{column}: {error}"), &output);
+                code = FAILED;
+                continue;
+            }
+        };
+        let mut formatted = String::new();
+        write_pretty(&grammar, &options, &mut formatted)
+            .expect("Writing to a String should not fail.");
+        let changed = formatted != source;
+
+        if check {
+            if changed && code == ACCEPTED {
+                code = REJECTED;
+            }
+        } else if path == "-" {
+            print!("{formatted}");
+        } else if changed {
+            if let Err(error) = std::fs::write(path, &formatted) {
+                report(path, &format!("couldn't write the file: {error}"), &output);
+                code = FAILED;
+                continue;
+            }
+        }
+
+        // the formatted grammar already went to the standard output
+        if output.verbosity == Verbosity::Quiet || (standard && !check) {
+            continue;
+        }
+        match output.format {
+            Format::Text => {
+                if check && changed {
+                    println!("{path}: not formatted");
+                } else if output.verbosity == Verbosity::Verbose {
+                    let status = if changed {
+                        "formatted"
+                    } else {
+                        "already formatted"
+                    };
+                    println!("{path}: {status}");
+                }
+            }
+            Format::Json => {
+                let mut json = String::from("{\"file\":");
+                write_string(&mut json, path).expect("Writing to a String should not fail.");
+                println!("{json},\"changed\":{changed}}}");
+            }
+        }
+    }
+    Ok(ExitCode::from(code))
+}
+
+fn report(path: &str, message: &str, output: &Output) {
+    if output.verbosity != Verbosity::Quiet {
+        eprintln!("error: {path}: {message}");
+    }
+}
@@ -0,0 +1,217 @@
+mod check;
+mod corpus;
+mod debug;
+mod fmt;
+mod parse;
+mod repl;
+
+use backus_naur_parser::bnf::grammar::{parse_grammar_checked, Grammar, GrammarError};
+use backus_naur_parser::json::write_string;
+use std::process::ExitCode;
+
+// the input matched the grammar
+pub const ACCEPTED: u8 = 0;
+// the input didn't match the grammar
+pub const REJECTED: u8 = 1;
+// the grammar is invalid, or a file couldn't be read, or the command line is wrong
+pub const FAILED: u8 = 2;
+
+const USAGE: &str = "usage: bnf <command> [options]
+
+commands:
+  parse --grammar <file> [<input>...]
+                                    parses the input file, or the standard input, with the grammar;
+                                    several files or directories are parsed as a corpus
+  check <grammar>...                lints the grammars
+  fmt [<grammar>...]                formats the grammars in place, or the standard input to the
+                                    standard output
+  repl [<grammar>]                  tests inputs against the grammar interactively, :help lists
+                                    the commands
+  debug --grammar <file> <input>    parses the input step by step, pausing at breakpoints, help
+                                    lists the commands
+
+options:
+  -g, --grammar <file>    the .bnf file of the grammar
+  -q, --quiet             prints nothing, only the exit code tells the result
+  -v, --verbose           prints details about the grammar, the input and the time taken
+  --format <text|json>    prints the result as text, or as a JSON object on a single line
+  -h, --help              prints this message
+
+parse options:
+  -t, --trace             prints every step of the parser before the result: the rules entered and
+                          left, the alternatives tried, the terminals matched or not, the backtracks
+  -p, --profile           prints how many times each rule and alternative was tried and matched, the
+                          terminals they compared, the backtracks they caused and the time they took
+  --tree <format>         prints the derivation of an accepted input instead of the result, as an
+                          indented text, JSON, an S-expression or XML: text, json, sexp or xml
+  -w, --watch             parses the corpus again whenever the grammar or one of its files changes,
+                          printing what started or stopped passing
+  --interval <ms>         how often the files are checked when watching (500), implies --watch
+
+check options:
+  --config <file>         reads the severities of the lints from lines like `unused-rule = allow`
+  -A, --allow <lint>      doesn't report the lint
+  -W, --warn <lint>       reports the lint as a warning
+  -D, --deny <lint>       reports the lint as an error, failing the check
+  lints: undefined-rule, unused-rule, duplicate-alternative, empty-in-sequence,
+         unproductive-alternative, left-recursion
+
+debug options:
+  -b, --break <rule|offset>
+                          pauses when the parser goes into the rule, or arrives at the offset; without
+                          breakpoints the parser pauses at its first step
+
+fmt options:
+  --check                 only tells which grammars aren't formatted
+  --width <n>             moves the alternatives going past this column to the next line (100)
+
+exit codes:
+  0  the input was accepted, no denied lint was found, or the grammars are formatted
+  1  the input was rejected, a denied lint was found, or a grammar isn't formatted
+  2  the grammar is invalid, a file couldn't be read, or the command line is wrong
+";
+
+pub fn run(arguments: &[String]) -> ExitCode {
+    // `--name=value` is the same as `--name value`
+    let arguments: Vec<String> = arguments
+        .iter()
+        .flat_map(|argument| match argument.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => {
+                vec![name.to_string(), value.to_string()]
+            }
+            _ => vec![argument.clone()],
+        })
+        .collect();
+
+    let result = match arguments.first().map(String::as_str) {
+        Some("parse") => parse::run(&arguments[1..]),
+        Some("check") => check::run(&arguments[1..]),
+        Some("fmt") => fmt::run(&arguments[1..]),
+        Some("repl") => repl::run(&arguments[1..]),
+        Some("debug") => debug::run(&arguments[1..]),
+        Some("-h" | "--help" | "help") => {
+            print!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Some(command) => Err(format!("unknown command `{command}`")),
+        None => Err("missing command".to_string()),
+    };
+    result.unwrap_or_else(|message| {
+        eprintln!("error: {message}\n\n{USAGE}");
+        ExitCode::from(FAILED)
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Verbosity {
+    Quiet,
+    #[default]
+    Normal,
+    Verbose,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    #[default]
+    Text,
+    Json,
+}
+
+// how much a command prints, and how
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Output {
+    pub verbosity: Verbosity,
+    pub format: Format,
+}
+
+impl Output {
+    // reads the option if it is one of the options shared by all the commands
+    pub fn parse_option(
+        &mut self,
+        argument: &str,
+        arguments: &mut std::slice::Iter<String>,
+    ) -> Result<bool, String> {
+        match argument {
+            "-q" | "--quiet" => self.verbosity = Verbosity::Quiet,
+            "-v" | "--verbose" => self.verbosity = Verbosity::Verbose,
+            "--format" => {
+                self.format = match value(argument, arguments)?.as_str() {
+                    "text" => Format::Text,
+                    "json" => Format::Json,
+                    format => return Err(format!("unknown format `{format}`")),
+                }
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+}
+
+// the value following an option
+pub fn value(option: &str, arguments: &mut std::slice::Iter<String>) -> Result<String, String> {
+    arguments
+        .next()
+        .cloned()
+        .ok_or_else(|| format!("missing value after `{option}`"))
+}
+
+// reads and parses the grammar, or reports why it couldn't
+pub fn load_grammar(path: &str, output: &Output) -> Option<Grammar> {
+    read_grammar(path, output, parse_grammar_checked).map(|(grammar, _)| grammar)
+}
+
+// same as `load_grammar` with another way to parse the grammar, also giving back its source
+pub fn read_grammar(
+    path: &str,
+    output: &Output,
+    parse: fn(&[char], usize) -> Result<Grammar, GrammarError>,
+) -> Option<(Grammar, String)> {
+    let source = match std::fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(error) => {
+            report_grammar_error(
+                path,
+                None,
+                &format!("couldn't read the file: {error}"),
+                output,
+            );
+            return None;
+        }
+    };
+    let chars: Vec<char> = source.chars().collect();
+    match parse(&chars, 0) {
+        Ok(grammar) => Some((grammar, source)),
+        Err(error) => {
+            let position = error.position(&chars);
+            report_grammar_error(path, Some(position), &error.to_string(), output);
+            None
+        }
+    }
+}
+
+fn report_grammar_error(
+    path: &str,
+    position: Option<(usize, usize)>,
+    message: &str,
+    output: &Output,
+) {
+    if output.verbosity == Verbosity::Quiet {
+        return;
+    }
+    match output.format {
+        Format::Text => match position {
+            Some((line, column)) => eprintln!("error: {path}:{line}:{column}: {message}"),
+            None => eprintln!("error: {path}: {message}"),
+        },
+        Format::Json => {
+            let mut json = String::from("{\"status\":\"grammar_error\",\"grammar\":");
+            write_string(&mut json, path).expect("Writing to a String should not fail.");
+            if let Some((line, column)) = position {
+                json.push_str(&format!(",\"line\":{line},\"column\":{column}"));
+            }
+            json.push_str(",\"message\":");
+            write_string(&mut json, message).expect("Writing to a String should not fail.");
+            println!("{json}}}");
+        }
+    }
+}
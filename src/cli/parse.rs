@@ -0,0 +1,190 @@
+use super::corpus;
+use super::{load_grammar, value, Format, Output, Verbosity, ACCEPTED, FAILED, REJECTED};
+use backus_naur_parser::bnf::grammar::Error;
+use backus_naur_parser::bnf::profile::{write_profile, write_profile_json};
+use backus_naur_parser::bnf::trace::TraceFormat;
+use backus_naur_parser::bnf::tree::{write_tree, write_tree_json, write_tree_sexp, write_tree_xml};
+use backus_naur_parser::json::write_string;
+use std::io::Read;
+use std::path::Path;
+use std::process::ExitCode;
+use std::time::Instant;
+
+// bnf parse --grammar <file> [<input>...] [--watch] [--trace | --profile] [--tree <format>]
+pub fn run(arguments: &[String]) -> Result<ExitCode, String> {
+    let mut grammar_path = None;
+    let mut input_paths = Vec::new();
+    let mut watch = None;
+    let mut trace = false;
+    let mut profile = false;
+    let mut tree = None;
+    let mut output = Output::default();
+    let mut arguments = arguments.iter();
+    while let Some(argument) = arguments.next() {
+        if output.parse_option(argument, &mut arguments)? {
+            continue;
+        }
+        match argument.as_str() {
+            "-g" | "--grammar" => grammar_path = Some(value(argument, &mut arguments)?),
+            "-t" | "--trace" => trace = true,
+            "-p" | "--profile" => profile = true,
+            "--tree" => {
+                let format = value(argument, &mut arguments)?;
+                if !["text", "json", "sexp", "xml"].contains(&format.as_str()) {
+                    return Err(format!("unknown tree format `{format}`"));
+                }
+                tree = Some(format);
+            }
+            "-w" | "--watch" => watch = Some(watch.unwrap_or(500)),
+            "--interval" => {
+                let interval = value(argument, &mut arguments)?;
+                watch = Some(
+                    interval
+                        .parse()
+                        .map_err(|_| format!("invalid interval `{interval}`"))?,
+                );
+            }
+            // a lone dash is the standard input
+            path if !path.starts_with('-') || path == "-" => input_paths.push(path.to_string()),
+            option => return Err(format!("unknown option `{option}`")),
+        }
+    }
+    let grammar_path = grammar_path.ok_or("missing `--grammar <file>`")?;
+    if trace && profile {
+        return Err("`--trace` and `--profile` can't be used together".to_string());
+    }
+
+    // several inputs, or directories of them, are parsed as a corpus
+    if watch.is_some()
+        || input_paths.len() > 1
+        || input_paths.iter().any(|path| Path::new(path).is_dir())
+    {
+        if input_paths.is_empty() || input_paths.iter().any(|path| path == "-") {
+            return Err("a corpus is made of files and directories".to_string());
+        }
+        if trace || profile || tree.is_some() {
+            return Err(
+                "only a single input can be traced, profiled or printed as a tree".to_string(),
+            );
+        }
+        return Ok(corpus::run(&grammar_path, &input_paths, &output, watch));
+    }
+    let input_path = input_paths.pop().unwrap_or_else(|| "-".to_string());
+
+    let Some(grammar) = load_grammar(&grammar_path, &output) else {
+        return Ok(ExitCode::from(FAILED));
+    };
+    let input = if input_path == "-" {
+        let mut input = String::new();
+        std::io::stdin().read_to_string(&mut input).map(|_| input)
+    } else {
+        std::fs::read_to_string(&input_path)
+    };
+    let input = match input {
+        Ok(input) => input,
+        Err(error) => {
+            if output.verbosity != Verbosity::Quiet {
+                eprintln!("error: {input_path}: couldn't read the input: {error}");
+            }
+            return Ok(ExitCode::from(FAILED));
+        }
+    };
+
+    let start = Instant::now();
+    let result = if trace && output.verbosity != Verbosity::Quiet {
+        // the steps are written as they happen, before the result and in the same format
+        let format = match output.format {
+            Format::Text => TraceFormat::Text,
+            Format::Json => TraceFormat::Json,
+        };
+        let stdout = std::io::BufWriter::new(std::io::stdout().lock());
+        let (result, written) = grammar.parse_traced(&input, stdout, format);
+        if let Err(error) = written {
+            eprintln!("error: couldn't write the trace: {error}");
+            return Ok(ExitCode::from(FAILED));
+        }
+        result
+    } else if profile {
+        let (result, profile) = grammar.parse_profiled(&input);
+        // the table comes before the result, like the trace
+        if output.verbosity != Verbosity::Quiet {
+            let mut text = String::new();
+            match output.format {
+                Format::Text => write_profile(&mut text, &grammar, &profile),
+                Format::Json => write_profile_json(&mut text, &grammar, &profile),
+            }
+            .expect("Writing to a String should not fail.");
+            print!("{text}");
+        }
+        result
+    } else {
+        grammar.parse(&input)
+    };
+    let elapsed = start.elapsed();
+
+    // the derivation of an accepted input replaces the result, for the tools reading it
+    if let (Some(format), Ok(choices)) = (&tree, &result) {
+        if output.verbosity != Verbosity::Quiet {
+            let chars: Vec<char> = input.chars().collect();
+            let tree = grammar.tree(choices);
+            let mut text = String::new();
+            match format.as_str() {
+                "json" => write_tree_json(&mut text, &grammar, &tree, &chars),
+                "sexp" => write_tree_sexp(&mut text, &grammar, &tree, &chars),
+                "xml" => write_tree_xml(&mut text, &grammar, &tree, &chars),
+                _ => write_tree(&mut text, &grammar, &tree, &chars),
+            }
+            .expect("Writing to a String should not fail.");
+            print!("{text}");
+        }
+        return Ok(ExitCode::from(ACCEPTED));
+    }
+
+    let (status, reason) = match result {
+        Ok(_) => ("accepted", None),
+        Err(Error::NoMatch) => ("rejected", Some("no_match")),
+        Err(Error::StackOverflow) => ("rejected", Some("stack_overflow")),
+        Err(Error::Stopped) => unreachable!("Only the debugger should stop a parse."),
+    };
+    let rules = grammar.labels.len();
+    let characters = input.chars().count();
+    let milliseconds = elapsed.as_secs_f64() * 1000.0;
+    match (output.verbosity, output.format) {
+        (Verbosity::Quiet, _) => {}
+        (verbosity, Format::Text) => {
+            if verbosity == Verbosity::Verbose {
+                println!(
+                    "grammar: {grammar_path} ({rules} rules, starting at <{}>)",
+                    grammar.references[&grammar.entrypoint()]
+                );
+                println!("input: {input_path} ({characters} characters)");
+                println!("time: {milliseconds:.3}ms");
+            }
+            match reason {
+                Some(reason) => println!("{status}: {}", reason.replace('_', " ")),
+                None => println!("{status}"),
+            }
+        }
+        (verbosity, Format::Json) => {
+            let mut json = format!("{{\"status\":\"{status}\"");
+            if let Some(reason) = reason {
+                json.push_str(&format!(",\"reason\":\"{reason}\""));
+            }
+            json.push_str(",\"grammar\":");
+            write_string(&mut json, &grammar_path).expect("Writing to a String should not fail.");
+            json.push_str(",\"input\":");
+            write_string(&mut json, &input_path).expect("Writing to a String should not fail.");
+            if verbosity == Verbosity::Verbose {
+                json.push_str(&format!(
+                    ",\"rules\":{rules},\"characters\":{characters},\"milliseconds\":{milliseconds:.3}"
+                ));
+            }
+            println!("{json}}}");
+        }
+    }
+    Ok(ExitCode::from(if result.is_ok() {
+        ACCEPTED
+    } else {
+        REJECTED
+    }))
+}
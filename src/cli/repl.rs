@@ -0,0 +1,233 @@
+use super::{load_grammar, value, Output, ACCEPTED, FAILED};
+use backus_naur_parser::bnf::grammar::{parse_expression, Error, Grammar};
+use backus_naur_parser::bnf::pretty::{write_pretty, Options};
+use backus_naur_parser::bnf::symbols::parse_spacings;
+use backus_naur_parser::bnf::tree::write_tree;
+use std::collections::HashMap;
+use std::io::{BufRead, IsTerminal, Write};
+use std::process::ExitCode;
+
+const HELP: &str = "type a line to parse it, or:
+  <rule> ::= ...     defines the rule, or replaces it
+  :parse <input>     parses the input even if it looks like a command or a rule
+  :start <rule>      parses from this rule instead of the first one
+  :rules             prints the grammar
+  :tree on|off       shows the derivation of the accepted inputs, or not
+  :reload            reads the grammar file again, forgetting the rules defined here
+  :help              prints this message
+  :quit              leaves, like the end of the input
+";
+
+struct Session {
+    path: Option<String>,
+    grammar: Grammar,
+    start: Option<String>,
+    tree: bool,
+}
+
+// bnf repl [--grammar <file>]
+pub fn run(arguments: &[String]) -> Result<ExitCode, String> {
+    let mut path = None;
+    let mut output = Output::default();
+    let mut arguments = arguments.iter();
+    while let Some(argument) = arguments.next() {
+        if output.parse_option(argument, &mut arguments)? {
+            continue;
+        }
+        match argument.as_str() {
+            "-g" | "--grammar" => path = Some(value(argument, &mut arguments)?),
+            path_argument if !path_argument.starts_with('-') => {
+                path = Some(path_argument.to_string())
+            }
+            option => return Err(format!("unknown option `{option}`")),
+        }
+    }
+
+    let grammar = match &path {
+        Some(path) => match load_grammar(path, &output) {
+            Some(grammar) => grammar,
+            None => return Ok(ExitCode::from(FAILED)),
+        },
+        None => Grammar {
+            references: HashMap::new(),
+            labels: Vec::new(),
+            maps: HashMap::new(),
+        },
+    };
+    let mut session = Session {
+        path,
+        grammar,
+        start: None,
+        tree: true,
+    };
+
+    // the prompt would only get in the way of piped sessions
+    let interactive = std::io::stdin().is_terminal();
+    if interactive {
+        println!(
+            "{} rules loaded, :help lists the commands",
+            session.grammar.labels.len()
+        );
+    }
+    let mut lines = std::io::stdin().lock().lines();
+    loop {
+        if interactive {
+            print!("> ");
+            std::io::stdout()
+                .flush()
+                .map_err(|error| error.to_string())?;
+        }
+        let Some(Ok(line)) = lines.next() else {
+            break;
+        };
+        if !session.run(&line, &output) {
+            break;
+        }
+    }
+    Ok(ExitCode::from(ACCEPTED))
+}
+
+impl Session {
+    // runs a line, telling whether to go on
+    fn run(&mut self, line: &str, output: &Output) -> bool {
+        let (command, rest) = line.split_once(' ').unwrap_or((line.trim_end(), ""));
+        let argument = rest.trim();
+        match command {
+            ":quit" | ":q" => return false,
+            ":help" => print!("{HELP}"),
+            // the spaces are part of the input
+            ":parse" => self.parse(rest),
+            ":rules" => {
+                let mut text = String::new();
+                write_pretty(&self.grammar, &Options::default(), &mut text)
+                    .expect("Writing to a String should not fail.");
+                print!("{text}");
+            }
+            ":start" => {
+                let label = argument.trim_start_matches('<').trim_end_matches('>');
+                if self.grammar.label_index(label).is_some() {
+                    self.start = Some(label.to_string());
+                    println!("parsing from <{label}>");
+                } else {
+                    println!("<{label}> doesn't exist");
+                }
+            }
+            ":tree" => match argument {
+                "on" => self.tree = true,
+                "off" => self.tree = false,
+                _ => println!("expected :tree on or :tree off"),
+            },
+            ":reload" => self.reload(output),
+            command if command.starts_with(':') => {
+                println!("unknown command {command}, :help lists the commands")
+            }
+            _ if line.trim_start().starts_with('<') && line.contains("::=") => self.define(line),
+            _ => self.parse(line),
+        }
+        true
+    }
+
+    fn parse(&self, input: &str) {
+        if self.grammar.labels.is_empty() {
+            println!("no rules yet, define one with <rule> ::= ...");
+            return;
+        }
+        if let Some(label_index) = self
+            .grammar
+            .references
+            .keys()
+            .find(|label_index| !self.grammar.maps.contains_key(label_index))
+        {
+            println!(
+                "<{}> is used but never defined",
+                self.grammar.references[label_index]
+            );
+            return;
+        }
+        let mut grammar = self.grammar.clone();
+        if let Some(label_index) = self
+            .start
+            .as_ref()
+            .and_then(|start| grammar.label_index(start))
+        {
+            grammar.set_entrypoint(label_index);
+        }
+        match grammar.parse(input) {
+            Ok(choices) => {
+                println!("accepted");
+                if self.tree {
+                    let chars: Vec<char> = input.chars().collect();
+                    let mut text = String::new();
+                    write_tree(&mut text, &grammar, &grammar.tree(&choices), &chars)
+                        .expect("Writing to a String should not fail.");
+                    print!("{text}");
+                }
+            }
+            Err(Error::NoMatch) => println!("rejected"),
+            Err(Error::StackOverflow) => println!("rejected: stack overflow"),
+            Err(Error::Stopped) => unreachable!("Only the debugger should stop a parse."),
+        }
+    }
+
+    fn define(&mut self, line: &str) {
+        let chars: Vec<char> = line.trim_end().chars().collect();
+        // the rule is parsed against copies, so a line that isn't valid changes nothing
+        let mut references = self.grammar.references.clone();
+        let mut references_reversed: HashMap<String, usize> = references
+            .iter()
+            .map(|(label_index, label)| (label.clone(), *label_index))
+            .collect();
+        let parsed = parse_expression(&chars, 0, &mut references, &mut references_reversed);
+        let (label_index, alternation) = match parsed {
+            Ok((index, label_index, alternation))
+                if parse_spacings(&chars, index) == chars.len() =>
+            {
+                (label_index, alternation)
+            }
+            _ => {
+                println!("invalid rule, expected <label> ::= alternatives");
+                return;
+            }
+        };
+
+        self.grammar.references = references;
+        let label = &self.grammar.references[&label_index];
+        if self.grammar.maps.insert(label_index, alternation).is_some() {
+            println!("redefined <{label}>");
+        } else {
+            self.grammar.labels.push(label_index);
+            println!("defined <{label}>");
+        }
+        let mut undefined: Vec<&usize> = self
+            .grammar
+            .references
+            .keys()
+            .filter(|label_index| !self.grammar.maps.contains_key(label_index))
+            .collect();
+        undefined.sort();
+        for label_index in undefined {
+            println!(
+                "<{}> isn't defined yet",
+                self.grammar.references[label_index]
+            );
+        }
+    }
+
+    fn reload(&mut self, output: &Output) {
+        let Some(path) = &self.path else {
+            println!("no grammar file to reload");
+            return;
+        };
+        // the errors are printed by load_grammar, and the current grammar is kept
+        if let Some(grammar) = load_grammar(path, output) {
+            self.grammar = grammar;
+            println!("{} rules loaded", self.grammar.labels.len());
+            if let Some(start) = &self.start {
+                if self.grammar.label_index(start).is_none() {
+                    println!("<{start}> is gone, parsing from the first rule again");
+                    self.start = None;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use super::{identifiers, write_body, Notation};
+use crate::bnf::element::Element;
+use crate::bnf::grammar::Grammar;
+use std::fmt::Write;
+
+const NOTATION: Notation = Notation {
+    alternation: " / ",
+    concatenation: " ",
+    optional_start: "[",
+    optional_end: "]",
+    empty: "\"\"",
+};
+
+// writes the grammar in RFC 5234 ABNF, with the CRLF line endings the RFC requires
+pub fn write_abnf(grammar: &Grammar, output: &mut dyn Write) -> std::fmt::Result {
+    let names = identifiers(grammar, sanitize, false);
+    for label_index in grammar.labels.iter() {
+        write!(output, "{} = ", names[label_index])?;
+        write_body(
+            output,
+            grammar,
+            *label_index,
+            &NOTATION,
+            &|output: &mut dyn Write, element: &Element| match element {
+                Element::Empty => write!(output, "\"\""),
+                Element::Literal(literal) => write_literal(output, literal),
+                Element::Range { start, end } => {
+                    write!(output, "%x{:02X}-{:02X}", *start as u32, *end as u32)
+                }
+                Element::Reference(target) => write!(output, "{}", names[target]),
+            },
+        )?;
+        write!(output, "\r\n")?;
+    }
+    Ok(())
+}
+
+fn write_literal(output: &mut dyn Write, literal: &str) -> std::fmt::Result {
+    // quoted strings are case insensitive in ABNF, so only the ones without letters keep the quotes,
+    // the others are written as their code points to keep matching the exact case
+    let quotable = literal
+        .chars()
+        .all(|c| matches!(c, ' '..='~') && c != '"' && !c.is_ascii_alphabetic());
+    if quotable {
+        return write!(output, "\"{literal}\"");
+    }
+    let codes: Vec<String> = literal
+        .chars()
+        .map(|c| format!("{:02X}", c as u32))
+        .collect();
+    write!(output, "%x{}", codes.join("."))
+}
+
+fn sanitize(label: &str) -> String {
+    // rule names are a letter followed by letters, digits and dashes
+    let name: String = label
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    if name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        name
+    } else {
+        format!("r-{name}")
+    }
+}
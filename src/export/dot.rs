@@ -0,0 +1,92 @@
+use crate::analysis::graph::{is_recursive, rule_graph, strongly_connected_components};
+use crate::bnf::element::Element;
+use crate::bnf::grammar::Grammar;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+// writes the reference graph of the rules in Graphviz DOT format:
+// edges carry the numbers of the alternatives using the reference, recursive cycles are drawn in red,
+// and the entrypoint has a double border
+pub fn write_dot(grammar: &Grammar, output: &mut dyn Write) -> std::fmt::Result {
+    let graph = rule_graph(grammar);
+    let mut cycle_of: HashMap<usize, usize> = HashMap::new();
+    for (i, component) in strongly_connected_components(grammar).iter().enumerate() {
+        if is_recursive(&graph, component) {
+            for label_index in component.iter() {
+                cycle_of.insert(*label_index, i);
+            }
+        }
+    }
+
+    writeln!(output, "digraph grammar {{")?;
+    writeln!(output, "  node [shape=box, fontname=\"monospace\"];")?;
+    writeln!(output, "  edge [fontname=\"monospace\"];")?;
+
+    for label_index in grammar.labels.iter() {
+        write!(output, "  ")?;
+        write_id(output, &grammar.references[label_index])?;
+        let mut attributes = Vec::new();
+        if *label_index == grammar.entrypoint() {
+            attributes.push("peripheries=2, style=bold");
+        }
+        if cycle_of.contains_key(label_index) {
+            attributes.push("color=red");
+        }
+        if attributes.is_empty() {
+            writeln!(output, ";")?;
+        } else {
+            writeln!(output, " [{}];", attributes.join(", "))?;
+        }
+    }
+
+    for label_index in grammar.labels.iter() {
+        // a single edge per target, listing every alternative the reference appears in
+        let mut targets: Vec<(usize, Vec<usize>)> = Vec::new();
+        for (i, sequence) in grammar.maps[label_index].sequences.iter().enumerate() {
+            for element in sequence.elements.iter() {
+                let Element::Reference(target) = element else {
+                    continue;
+                };
+                match targets.iter_mut().find(|(existing, _)| existing == target) {
+                    Some((_, alternatives)) => {
+                        if !alternatives.contains(&i) {
+                            alternatives.push(i);
+                        }
+                    }
+                    None => targets.push((*target, vec![i])),
+                }
+            }
+        }
+
+        for (target, alternatives) in targets {
+            write!(output, "  ")?;
+            write_id(output, &grammar.references[label_index])?;
+            write!(output, " -> ")?;
+            write_id(output, &grammar.references[&target])?;
+            let alternatives: Vec<String> =
+                alternatives.iter().map(|i| (i + 1).to_string()).collect();
+            write!(output, " [label=\"{}\"", alternatives.join(","))?;
+            // the edge is part of a cycle when both ends are in the same recursive component
+            if cycle_of.contains_key(label_index)
+                && cycle_of.get(label_index) == cycle_of.get(&target)
+            {
+                write!(output, ", color=red, fontcolor=red")?;
+            }
+            writeln!(output, "];")?;
+        }
+    }
+
+    writeln!(output, "}}")
+}
+
+fn write_id(output: &mut dyn Write, label: &str) -> std::fmt::Result {
+    write!(output, "\"")?;
+    for c in label.chars() {
+        match c {
+            '"' => write!(output, "\\\"")?,
+            '\\' => write!(output, "\\\\")?,
+            c => write!(output, "{c}")?,
+        }
+    }
+    write!(output, "\"")
+}
@@ -0,0 +1,67 @@
+use super::{identifiers, quoted_pieces, write_body, Notation};
+use crate::bnf::element::Element;
+use crate::bnf::grammar::Grammar;
+use std::fmt::Write;
+
+const NOTATION: Notation = Notation {
+    alternation: " | ",
+    concatenation: ", ",
+    optional_start: "[",
+    optional_end: "]",
+    empty: "(* empty *)",
+};
+
+// past this many characters a range is written as a special sequence instead of listing its characters
+const MAX_EXPANDED_RANGE: u32 = 128;
+
+// writes the grammar in ISO/IEC 14977 EBNF
+pub fn write_iso_ebnf(grammar: &Grammar, output: &mut dyn Write) -> std::fmt::Result {
+    let names = identifiers(grammar, sanitize, true);
+    for label_index in grammar.labels.iter() {
+        write!(output, "{} = ", names[label_index])?;
+        write_body(
+            output,
+            grammar,
+            *label_index,
+            &NOTATION,
+            &|output: &mut dyn Write, element: &Element| match element {
+                Element::Empty => write!(output, "(* empty *)"),
+                Element::Literal(literal) => {
+                    write!(output, "{}", quoted_pieces(literal).join(", "))
+                }
+                Element::Range { start, end } => write_range(output, *start, *end),
+                Element::Reference(target) => write!(output, "{}", names[target]),
+            },
+        )?;
+        writeln!(output, " ;")?;
+    }
+    Ok(())
+}
+
+fn write_range(output: &mut dyn Write, start: char, end: char) -> std::fmt::Result {
+    // the notation has no ranges, so small ones are listed character by character
+    let (start, end) = (start as u32, end as u32);
+    if end < start || end - start >= MAX_EXPANDED_RANGE {
+        return write!(output, "? characters U+{start:04X} to U+{end:04X} ?");
+    }
+    let characters: Vec<String> = (start..=end)
+        .filter_map(char::from_u32)
+        .flat_map(|c| quoted_pieces(&c.to_string()))
+        .collect();
+    write!(output, "({})", characters.join(" | "))
+}
+
+fn sanitize(label: &str) -> String {
+    // meta identifiers are a letter followed by letters and digits, spaces are allowed in between
+    let words: Vec<String> = label
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_string)
+        .collect();
+    let name = words.join(" ");
+    if name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        name
+    } else {
+        format!("rule {name}").trim_end().to_string()
+    }
+}
@@ -0,0 +1,127 @@
+pub mod abnf;
+pub mod dot;
+pub mod iso_ebnf;
+pub mod pest;
+pub mod railroad;
+pub mod rust;
+pub mod tree_sitter;
+pub mod w3c_ebnf;
+
+use crate::bnf::element::Element;
+use crate::bnf::grammar::Grammar;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+// turns every label into an identifier valid in the target notation, without two rules sharing one
+fn identifiers(
+    grammar: &Grammar,
+    sanitize: impl Fn(&str) -> String,
+    case_sensitive: bool,
+) -> HashMap<usize, String> {
+    let normalize = |identifier: &str| {
+        if case_sensitive {
+            identifier.to_string()
+        } else {
+            identifier.to_lowercase()
+        }
+    };
+    let mut identifiers: HashMap<usize, String> = HashMap::new();
+    for label_index in grammar.labels.iter() {
+        let base = sanitize(&grammar.references[label_index]);
+        let mut identifier = base.clone();
+        let mut counter = 2;
+        while identifiers
+            .values()
+            .any(|existing| normalize(existing) == normalize(&identifier))
+        {
+            identifier = format!("{base}{counter}");
+            counter += 1;
+        }
+        identifiers.insert(*label_index, identifier);
+    }
+    identifiers
+}
+
+// quotes the literal with double quotes, or single quotes when it contains double quotes,
+// splitting it into several strings when it contains both
+fn quoted_pieces(literal: &str) -> Vec<String> {
+    let mut pieces = Vec::new();
+    let mut piece = String::new();
+    // the quote of a piece is decided by the first quote character found in it
+    let mut quote: Option<char> = None;
+    for c in literal.chars() {
+        match (quote, c) {
+            (None, '"') => quote = Some('\''),
+            (None, '\'') => quote = Some('"'),
+            (Some(current), c) if c == current => {
+                pieces.push(format!("{current}{piece}{current}"));
+                piece.clear();
+                quote = Some(if c == '"' { '\'' } else { '"' });
+            }
+            _ => {}
+        }
+        piece.push(c);
+    }
+    if !piece.is_empty() {
+        let quote = quote.unwrap_or('"');
+        pieces.push(format!("{quote}{piece}{quote}"));
+    }
+    pieces
+}
+
+// the syntax of the standard notations that differs from one to another
+struct Notation {
+    alternation: &'static str,
+    concatenation: &'static str,
+    optional_start: &'static str,
+    optional_end: &'static str,
+    // written for a rule that only matches the empty string
+    empty: &'static str,
+}
+
+// writes the alternatives of the rule, an ε alternative making the others optional
+// since none of the notations has a symbol for the empty string
+fn write_body(
+    output: &mut dyn Write,
+    grammar: &Grammar,
+    label_index: usize,
+    notation: &Notation,
+    write_element: &dyn Fn(&mut dyn Write, &Element) -> std::fmt::Result,
+) -> std::fmt::Result {
+    let mut optional = false;
+    let mut sequences = Vec::new();
+    for sequence in grammar.maps[&label_index].sequences.iter() {
+        let elements: Vec<&Element> = sequence
+            .elements
+            .iter()
+            .filter(|element| !matches!(element, Element::Empty))
+            .collect();
+        if elements.is_empty() {
+            optional = true;
+        } else {
+            sequences.push(elements);
+        }
+    }
+
+    if sequences.is_empty() {
+        return write!(output, "{}", notation.empty);
+    }
+    if optional {
+        write!(output, "{}", notation.optional_start)?;
+    }
+    for (i, elements) in sequences.iter().enumerate() {
+        if i != 0 {
+            write!(output, "{}", notation.alternation)?;
+        }
+        for (j, element) in elements.iter().enumerate() {
+            if j != 0 {
+                write!(output, "{}", notation.concatenation)?;
+            }
+            write_element(output, element)?;
+        }
+    }
+    if optional {
+        write!(output, "{}", notation.optional_end)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,194 @@
+use super::{identifiers, write_body, Notation};
+use crate::analysis::first::{
+    first_sets, follow_sets, left_recursive_rules, overlap, sequence_first,
+};
+use crate::bnf::element::Element;
+use crate::bnf::grammar::Grammar;
+use std::fmt::{self, Write};
+
+const NOTATION: Notation = Notation {
+    alternation: " | ",
+    concatenation: " ~ ",
+    optional_start: "(",
+    optional_end: ")?",
+    empty: "\"\"",
+};
+
+// rust keywords can't name a rule, since every rule becomes a variant of the generated enum
+const RUST_KEYWORDS: [&str; 51] = [
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in",
+    "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "Self", "static", "struct", "super", "trait", "true", "try", "type",
+    "typeof", "union", "unsafe", "unsized", "use", "virtual", "where", "while",
+];
+
+// where the ordered choice of PEG doesn't match what the backtracking parser of this crate accepts
+#[derive(Debug, Clone)]
+pub enum Warning {
+    // pest refuses left recursive rules
+    LeftRecursion {
+        rule: String,
+    },
+    // both alternatives (counted from 0) can start with the same character: PEG commits to the first one
+    // as soon as it matches and never tries the second one, even when the rest of the input then fails
+    OrderedChoice {
+        rule: String,
+        first: usize,
+        second: usize,
+    },
+    // the optional part of the rule is greedy, it won't give back characters the following elements need
+    Greedy {
+        rule: String,
+    },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Warning::LeftRecursion { rule } => {
+                write!(f, "<{rule}> is left recursive, pest won't accept it")
+            }
+            Warning::OrderedChoice {
+                rule,
+                first,
+                second,
+            } => write!(
+                f,
+                "alternatives {} and {} of <{rule}> can start alike, pest never tries the second one once the first matched",
+                first + 1,
+                second + 1
+            ),
+            Warning::Greedy { rule } => write!(
+                f,
+                "<{rule}> can match the empty string or start like what follows it, pest matches as much as it can and won't backtrack"
+            ),
+        }
+    }
+}
+
+// writes the grammar as a pest file, the entrypoint being wrapped in a rule matching the whole input,
+// and the warnings as comments at the top
+pub fn write_pest(grammar: &Grammar, output: &mut dyn Write) -> fmt::Result {
+    let names = identifiers(grammar, sanitize, true);
+    let mut main = String::from("main");
+    let mut counter = 2;
+    while names.values().any(|name| *name == main) {
+        main = format!("main{counter}");
+        counter += 1;
+    }
+
+    for warning in pest_warnings(grammar) {
+        writeln!(output, "// warning: {warning}")?;
+    }
+    writeln!(
+        output,
+        "{main} = {{ SOI ~ {} ~ EOI }}",
+        names[&grammar.entrypoint()]
+    )?;
+    for label_index in grammar.labels.iter() {
+        write!(output, "{} = {{ ", names[label_index])?;
+        write_body(
+            output,
+            grammar,
+            *label_index,
+            &NOTATION,
+            &|output: &mut dyn Write, element: &Element| match element {
+                Element::Empty => write!(output, "\"\""),
+                Element::Literal(literal) => write!(output, "\"{}\"", escape(literal, '"')),
+                Element::Range { start, end } => write!(
+                    output,
+                    "'{}'..'{}'",
+                    escape(&start.to_string(), '\''),
+                    escape(&end.to_string(), '\'')
+                ),
+                Element::Reference(target) => write!(output, "{}", names[target]),
+            },
+        )?;
+        writeln!(output, " }}")?;
+    }
+    Ok(())
+}
+
+pub fn pest_warnings(grammar: &Grammar) -> Vec<Warning> {
+    let nullable = grammar.nullable_labels();
+    let first = first_sets(grammar, &nullable);
+    let follow = follow_sets(grammar, &first, &nullable);
+    let label = |label_index: &usize| grammar.references[label_index].clone();
+
+    let mut warnings: Vec<Warning> = left_recursive_rules(grammar)
+        .iter()
+        .map(|label_index| Warning::LeftRecursion {
+            rule: label(label_index),
+        })
+        .collect();
+    for label_index in grammar.labels.iter() {
+        let sequences = &grammar.maps[label_index].sequences;
+        let starts: Vec<_> = sequences
+            .iter()
+            .map(|sequence| sequence_first(&sequence.elements, &first, &nullable))
+            .collect();
+        // the ε alternatives are written as an optional around the others, they don't take part in the choice
+        let empty = |i: usize| {
+            sequences[i]
+                .elements
+                .iter()
+                .all(|element| matches!(element, Element::Empty))
+        };
+        for i in 0..sequences.len() {
+            for j in i + 1..sequences.len() {
+                if !empty(i) && !empty(j) && overlap(&starts[i], &starts[j]) {
+                    warnings.push(Warning::OrderedChoice {
+                        rule: label(label_index),
+                        first: i,
+                        second: j,
+                    });
+                }
+            }
+        }
+        if (0..sequences.len()).any(empty) && overlap(&first[label_index], &follow[label_index]) {
+            warnings.push(Warning::Greedy {
+                rule: label(label_index),
+            });
+        }
+    }
+    warnings
+}
+
+fn escape(text: &str, quote: char) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c == quote => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c if c.is_control() => escaped.push_str(&format!("\\u{{{:X}}}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn sanitize(label: &str) -> String {
+    // identifiers are letters, digits and underscores, not starting with a digit;
+    // the names without lowercase letters are lowercased since the builtin rules of pest are all uppercase
+    let mut name: String = label
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if !name.chars().any(|c| c.is_ascii_lowercase()) {
+        name = name.to_lowercase();
+    }
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        name = format!("_{name}");
+    }
+    if RUST_KEYWORDS.contains(&name.as_str()) {
+        name.push_str("_rule");
+    }
+    name
+}
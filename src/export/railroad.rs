@@ -0,0 +1,360 @@
+use crate::bnf::element::Element;
+use crate::bnf::format::Format;
+use crate::bnf::grammar::Grammar;
+use crate::xml::write_escaped;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+const CHAR_WIDTH: usize = 8;
+const BOX_PADDING: usize = 10;
+const BOX_HEIGHT: usize = 24;
+const GAP: usize = 10;
+const ARC: usize = 10;
+const MARGIN: usize = 20;
+const TITLE_HEIGHT: usize = 30;
+
+const STYLE: &str = "path { fill: none; stroke: #333; stroke-width: 2; } \
+rect { stroke: #333; stroke-width: 2; } \
+rect.terminal { fill: #d9f2d9; } \
+rect.reference { fill: #d9e6f2; } \
+text { font-family: monospace; font-size: 14px; } \
+text.label { text-anchor: middle; dominant-baseline: central; } \
+text.title { font-weight: bold; }";
+
+enum Node {
+    Terminal(String),
+    Reference { label: String, file: String },
+    Skip,
+    Sequence(Vec<Node>),
+    Choice(Vec<Node>),
+}
+
+// the size of a node, the line going through it being `entry` below its top
+struct Size {
+    width: usize,
+    height: usize,
+    entry: usize,
+}
+
+impl Node {
+    fn size(&self) -> Size {
+        match self {
+            Node::Terminal(text) | Node::Reference { label: text, .. } => Size {
+                width: text.chars().count() * CHAR_WIDTH + 2 * BOX_PADDING,
+                height: BOX_HEIGHT,
+                entry: BOX_HEIGHT / 2,
+            },
+            Node::Skip => Size {
+                width: 0,
+                height: 0,
+                entry: 0,
+            },
+            Node::Sequence(nodes) => {
+                let sizes: Vec<Size> = nodes.iter().map(Node::size).collect();
+                let entry = sizes.iter().map(|size| size.entry).max().unwrap_or(0);
+                let below = sizes
+                    .iter()
+                    .map(|size| size.height - size.entry)
+                    .max()
+                    .unwrap_or(0);
+                Size {
+                    width: sizes.iter().map(|size| size.width).sum::<usize>()
+                        + GAP * nodes.len().saturating_sub(1),
+                    height: entry + below,
+                    entry,
+                }
+            }
+            Node::Choice(nodes) => {
+                let sizes: Vec<Size> = nodes.iter().map(Node::size).collect();
+                let mut height = sizes[0].height;
+                for size in sizes[1..].iter() {
+                    height += GAP.max(ARC) + size.height.max(ARC);
+                }
+                Size {
+                    width: sizes.iter().map(|size| size.width).max().unwrap_or(0) + 4 * ARC,
+                    height,
+                    entry: sizes[0].entry,
+                }
+            }
+        }
+    }
+
+    // draws the node with its line entering on the left at (x, y)
+    fn render(&self, output: &mut dyn Write, x: usize, y: usize) -> std::fmt::Result {
+        match self {
+            Node::Terminal(text) => render_box(output, x, y, text, "terminal", None),
+            Node::Reference { label, file } => {
+                render_box(output, x, y, label, "reference", Some(file))
+            }
+            Node::Skip => Ok(()),
+            Node::Sequence(nodes) => {
+                let mut x = x;
+                for (i, node) in nodes.iter().enumerate() {
+                    if i != 0 {
+                        line(output, x, y, x + GAP, y)?;
+                        x += GAP;
+                    }
+                    node.render(output, x, y)?;
+                    x += node.size().width;
+                }
+                Ok(())
+            }
+            Node::Choice(nodes) => {
+                let size = self.size();
+                let right = x + size.width;
+
+                // the first alternative stays on the main line
+                let first = nodes[0].size();
+                line(output, x, y, x + 2 * ARC, y)?;
+                nodes[0].render(output, x + 2 * ARC, y)?;
+                line(output, x + 2 * ARC + first.width, y, right, y)?;
+
+                // the others hang below, joined to the main line by curves on both sides
+                let mut bottom = y + first.height - first.entry;
+                for node in nodes[1..].iter() {
+                    let node_size = node.size();
+                    let node_y = bottom + GAP.max(ARC) + node_size.entry.max(ARC);
+                    write!(
+                        output,
+                        "<path d=\"M{x} {y} Q{} {y} {} {} L{} {} Q{} {node_y} {} {node_y}\"/>",
+                        x + ARC,
+                        x + ARC,
+                        y + ARC,
+                        x + ARC,
+                        node_y - ARC,
+                        x + ARC,
+                        x + 2 * ARC
+                    )?;
+                    node.render(output, x + 2 * ARC, node_y)?;
+                    write!(
+                        output,
+                        "<path d=\"M{} {node_y} L{} {node_y} Q{} {node_y} {} {} L{} {} Q{} {y} {right} {y}\"/>",
+                        x + 2 * ARC + node_size.width,
+                        right - 2 * ARC,
+                        right - ARC,
+                        right - ARC,
+                        node_y - ARC,
+                        right - ARC,
+                        y + ARC,
+                        right - ARC
+                    )?;
+                    bottom = node_y + node_size.height.max(ARC) - node_size.entry.max(ARC);
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn line(output: &mut dyn Write, x1: usize, y1: usize, x2: usize, y2: usize) -> std::fmt::Result {
+    if x1 == x2 && y1 == y2 {
+        return Ok(());
+    }
+    write!(output, "<path d=\"M{x1} {y1} L{x2} {y2}\"/>")
+}
+
+fn render_box(
+    output: &mut dyn Write,
+    x: usize,
+    y: usize,
+    text: &str,
+    class: &str,
+    link: Option<&str>,
+) -> std::fmt::Result {
+    let width = text.chars().count() * CHAR_WIDTH + 2 * BOX_PADDING;
+    let top = y - BOX_HEIGHT / 2;
+    // terminals are rounded, references are plain rectangles
+    let radius = if class == "terminal" {
+        BOX_HEIGHT / 2
+    } else {
+        0
+    };
+    if let Some(link) = link {
+        write!(output, "<a href=\"")?;
+        write_escaped(output, link)?;
+        write!(output, "\" xlink:href=\"")?;
+        write_escaped(output, link)?;
+        write!(output, "\">")?;
+    }
+    write!(
+        output,
+        "<rect class=\"{class}\" x=\"{x}\" y=\"{top}\" width=\"{width}\" height=\"{BOX_HEIGHT}\" rx=\"{radius}\"/>"
+    )?;
+    write!(
+        output,
+        "<text class=\"label\" x=\"{}\" y=\"{y}\">",
+        x + width / 2
+    )?;
+    write_escaped(output, text)?;
+    write!(output, "</text>")?;
+    if link.is_some() {
+        write!(output, "</a>")?;
+    }
+    Ok(())
+}
+
+// the svg file of every rule, named after the rule with the characters unsafe in a path replaced
+pub fn file_names(grammar: &Grammar) -> HashMap<usize, String> {
+    let mut names: HashMap<usize, String> = HashMap::new();
+    for label_index in grammar.labels.iter() {
+        let base: String = grammar.references[label_index]
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        let mut name = format!("{base}.svg");
+        let mut counter = 2;
+        while names.values().any(|existing| existing == &name) {
+            name = format!("{base}-{counter}.svg");
+            counter += 1;
+        }
+        names.insert(*label_index, name);
+    }
+    names
+}
+
+fn rule_node(grammar: &Grammar, label_index: usize, files: &HashMap<usize, String>) -> Node {
+    let mut alternatives: Vec<Node> = grammar.maps[&label_index]
+        .sequences
+        .iter()
+        .map(|sequence| {
+            let mut nodes: Vec<Node> = Vec::new();
+            for element in sequence.elements.iter() {
+                match element {
+                    // ε doesn't draw anything, so a sequence made of it becomes a bypass
+                    Element::Empty => {}
+                    Element::Reference(target) => nodes.push(Node::Reference {
+                        label: grammar.references[target].clone(),
+                        file: files[target].clone(),
+                    }),
+                    Element::Range { .. } | Element::Literal(_) => {
+                        let mut text = String::new();
+                        element
+                            .format(&mut text, grammar)
+                            .expect("Writing to a String should not fail.");
+                        nodes.push(Node::Terminal(text));
+                    }
+                }
+            }
+            match nodes.len() {
+                0 => Node::Skip,
+                1 => nodes.pop().expect("There should be a node."),
+                _ => Node::Sequence(nodes),
+            }
+        })
+        .collect();
+    if alternatives.len() == 1 {
+        alternatives.pop().expect("There should be an alternative.")
+    } else {
+        Node::Choice(alternatives)
+    }
+}
+
+pub fn rule_svg(grammar: &Grammar, label_index: usize) -> String {
+    let files = file_names(grammar);
+    let node = rule_node(grammar, label_index, &files);
+    let size = node.size();
+    let width = size.width + 2 * MARGIN + 4 * GAP;
+    let height = size.height + 2 * MARGIN + TITLE_HEIGHT;
+    let y = MARGIN + TITLE_HEIGHT + size.entry;
+
+    let mut output = String::new();
+    let mut write_svg = || -> std::fmt::Result {
+        write!(
+            output,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" xmlns:xlink=\"http://www.w3.org/1999/xlink\" width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {width} {height}\">"
+        )?;
+        write!(output, "<style>{STYLE}</style>")?;
+        write!(
+            output,
+            "<text class=\"title\" x=\"{MARGIN}\" y=\"{}\">&lt;",
+            MARGIN + 10
+        )?;
+        write_escaped(&mut output, &grammar.references[&label_index])?;
+        write!(output, "&gt;</text>")?;
+
+        // the start and the end of the rule are marked with a double bar
+        write!(
+            output,
+            "<path d=\"M{MARGIN} {} L{MARGIN} {} M{} {} L{} {}\"/>",
+            y - ARC,
+            y + ARC,
+            MARGIN + 4,
+            y - ARC,
+            MARGIN + 4,
+            y + ARC
+        )?;
+        line(&mut output, MARGIN, y, MARGIN + 2 * GAP, y)?;
+        node.render(&mut output, MARGIN + 2 * GAP, y)?;
+        let end = MARGIN + 2 * GAP + size.width;
+        line(&mut output, end, y, end + 2 * GAP, y)?;
+        write!(
+            output,
+            "<path d=\"M{} {} L{} {} M{} {} L{} {}\"/>",
+            end + 2 * GAP - 4,
+            y - ARC,
+            end + 2 * GAP - 4,
+            y + ARC,
+            end + 2 * GAP,
+            y - ARC,
+            end + 2 * GAP,
+            y + ARC
+        )?;
+        writeln!(output, "</svg>")
+    };
+    write_svg().expect("Writing to a String should not fail.");
+    output
+}
+
+pub fn index_html(grammar: &Grammar) -> String {
+    let files = file_names(grammar);
+    let mut output = String::new();
+    let mut write_html = || -> std::fmt::Result {
+        writeln!(output, "<!DOCTYPE html>")?;
+        writeln!(
+            output,
+            "<html><head><meta charset=\"utf-8\"><title>Grammar</title></head><body>"
+        )?;
+        writeln!(output, "<h1>Grammar</h1>")?;
+        for (i, label_index) in grammar.labels.iter().enumerate() {
+            write!(output, "<h2 id=\"")?;
+            write_escaped(&mut output, &files[label_index])?;
+            write!(output, "\"><a href=\"")?;
+            write_escaped(&mut output, &files[label_index])?;
+            write!(output, "\">&lt;")?;
+            write_escaped(&mut output, &grammar.references[label_index])?;
+            write!(output, "&gt;</a>")?;
+            if i == 0 {
+                write!(output, " (entrypoint)")?;
+            }
+            writeln!(output, "</h2>")?;
+            // an object keeps the links of the diagram working, unlike an image
+            write!(output, "<object type=\"image/svg+xml\" data=\"")?;
+            write_escaped(&mut output, &files[label_index])?;
+            writeln!(output, "\"></object>")?;
+        }
+        writeln!(output, "</body></html>")
+    };
+    write_html().expect("Writing to a String should not fail.");
+    output
+}
+
+// writes the diagram of every rule and the index page linking them into the directory
+pub fn write_railroad(grammar: &Grammar, directory: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(directory)?;
+    let files = file_names(grammar);
+    for label_index in grammar.labels.iter() {
+        fs::write(
+            directory.join(&files[label_index]),
+            rule_svg(grammar, *label_index),
+        )?;
+    }
+    fs::write(directory.join("index.html"), index_html(grammar))
+}
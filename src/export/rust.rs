@@ -0,0 +1,227 @@
+use super::identifiers;
+use crate::bnf::element::Element;
+use crate::bnf::format::Format;
+use crate::bnf::grammar::{parse_grammar, Grammar};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(std::io::Error),
+    InvalidGrammar,
+}
+
+// the part of the generated parser that doesn't depend on the grammar: every rule is a function
+// trying its alternatives in order, and handing the index where one of them ends to what comes after
+// the rule, so that a later failure backtracks into the rule like in `Grammar::parse`
+const PARSER: &str = r#"#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    StackOverflow,
+    NoMatch,
+}
+
+// like `Grammar::parse`, the parser gives up when this many rules are being matched at once
+const MAX_DEPTH: usize = 1_000_000;
+// a rule only returns once everything after it failed, so the parser runs on its own thread with a
+// stack large enough for deeply nested rules
+const STACK_SIZE: usize = 1024 * 1024 * 1024;
+
+struct Parser {
+    chars: Vec<char>,
+    depth: usize,
+}
+
+// what comes after an element, given the index where the element ends, returning whether the whole
+// input matched
+type Next<'a> = &'a mut dyn FnMut(&mut Parser, usize) -> Result<bool, Error>;
+
+impl Parser {
+    fn enter(&mut self) -> Result<(), Error> {
+        if self.depth == MAX_DEPTH {
+            return Err(Error::StackOverflow);
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn leave(&mut self, matched: bool) -> Result<bool, Error> {
+        self.depth -= 1;
+        Ok(matched)
+    }
+
+    #[allow(dead_code)]
+    fn literal(&mut self, index: usize, literal: &[char], next: Next) -> Result<bool, Error> {
+        if self.chars.get(index..index + literal.len()) == Some(literal) {
+            next(self, index + literal.len())
+        } else {
+            Ok(false)
+        }
+    }
+
+    #[allow(dead_code)]
+    fn range(&mut self, index: usize, start: char, end: char, next: Next) -> Result<bool, Error> {
+        match self.chars.get(index) {
+            Some(c) if start <= *c && *c <= end => next(self, index + 1),
+            _ => Ok(false),
+        }
+    }
+}
+
+pub fn parse(input: &str) -> Result<(), Error> {
+    let mut parser = Parser {
+        chars: input.chars().collect(),
+        depth: 0,
+    };
+    let matched = std::thread::scope(|scope| {
+        std::thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn_scoped(scope, || {
+                entrypoint(&mut parser, 0, &mut |parser, index| {
+                    Ok(index == parser.chars.len())
+                })
+            })
+            .expect("The parser thread should start.")
+            .join()
+            .expect("The parser thread should not panic.")
+    })?;
+    if matched {
+        Ok(())
+    } else {
+        Err(Error::NoMatch)
+    }
+}
+"#;
+
+// writes a Rust module parsing the language of the grammar without interpreting it at runtime, with a
+// recursive descent function per rule, its `parse` function accepting and rejecting the same inputs as
+// `Grammar::parse`
+pub fn write_rust(grammar: &Grammar, output: &mut dyn Write) -> std::fmt::Result {
+    let names = identifiers(grammar, sanitize, true);
+
+    writeln!(output, "// generated from a BNF grammar, do not edit")?;
+    writeln!(output)?;
+    write!(output, "{PARSER}")?;
+    writeln!(output)?;
+    writeln!(
+        output,
+        "fn entrypoint(parser: &mut Parser, index: usize, next: Next) -> Result<bool, Error> {{"
+    )?;
+    writeln!(
+        output,
+        "    {}(parser, index, next)",
+        names[&grammar.entrypoint()]
+    )?;
+    writeln!(output, "}}")?;
+
+    for label_index in grammar.labels.iter() {
+        let alternation = &grammar.maps[label_index];
+        let mut rule = String::new();
+        alternation.format(&mut rule, grammar)?;
+        writeln!(output)?;
+        writeln!(
+            output,
+            "// <{}> ::= {}",
+            grammar.references[label_index],
+            rule.escape_default()
+        )?;
+        writeln!(
+            output,
+            "fn {}(parser: &mut Parser, index: usize, next: Next) -> Result<bool, Error> {{",
+            names[label_index]
+        )?;
+        writeln!(output, "    parser.enter()?;")?;
+        for (i, sequence) in alternation.sequences.iter().enumerate() {
+            if i == 0 {
+                write!(output, "    let matched = ")?;
+            } else {
+                write!(output, "        || ")?;
+            }
+            // ε matches without moving, so it is left out of the sequences
+            let elements: Vec<&Element> = sequence
+                .elements
+                .iter()
+                .filter(|element| !matches!(element, Element::Empty))
+                .collect();
+            // the first alternative starts the statement, the others are indented below it
+            write_sequence(output, &names, &elements, if i == 0 { 1 } else { 2 })?;
+            if i + 1 == alternation.sequences.len() {
+                writeln!(output, "?;")?;
+            } else {
+                writeln!(output, "?")?;
+            }
+        }
+        writeln!(output, "    parser.leave(matched)")?;
+        writeln!(output, "}}")?;
+    }
+    Ok(())
+}
+
+// writes the call matching the first element, with the rest of the sequence as what comes after it,
+// the closures getting deeper by one level of indentation per element
+fn write_sequence(
+    output: &mut dyn Write,
+    names: &HashMap<usize, String>,
+    elements: &[&Element],
+    depth: usize,
+) -> std::fmt::Result {
+    let Some((element, rest)) = elements.split_first() else {
+        return write!(output, "next(parser, index)");
+    };
+    let next = if rest.is_empty() {
+        "next"
+    } else {
+        "&mut |parser, index| {"
+    };
+    match element {
+        Element::Literal(literal) => {
+            let characters: Vec<String> = literal.chars().map(|c| format!("{c:?}")).collect();
+            write!(
+                output,
+                "parser.literal(index, &[{}], {next}",
+                characters.join(", ")
+            )?
+        }
+        Element::Range { start, end } => {
+            write!(output, "parser.range(index, {start:?}, {end:?}, {next}")?
+        }
+        Element::Reference(target) => write!(output, "{}(parser, index, {next}", names[target])?,
+        Element::Empty => unreachable!("ε should be left out."),
+    }
+    if rest.is_empty() {
+        return write!(output, ")");
+    }
+    let indentation = "    ".repeat(depth);
+    writeln!(output)?;
+    write!(output, "{indentation}    ")?;
+    write_sequence(output, names, rest, depth + 1)?;
+    writeln!(output)?;
+    write!(output, "{indentation}}})")
+}
+
+// for build.rs: generates the parser of a .bnf file, to be included with
+// `include!(concat!(env!("OUT_DIR"), "/grammar.rs"))`, and reruns the build when the grammar changes
+pub fn build_parser(grammar_path: &Path, output_path: &Path) -> Result<(), Error> {
+    println!("cargo:rerun-if-changed={}", grammar_path.display());
+    let source = std::fs::read_to_string(grammar_path).map_err(Error::Io)?;
+    let chars: Vec<char> = source.chars().collect();
+    let grammar = parse_grammar(&chars, 0).map_err(|_| Error::InvalidGrammar)?;
+    let mut code = String::new();
+    write_rust(&grammar, &mut code).expect("Writing to a String should not fail.");
+    std::fs::write(output_path, code).map_err(Error::Io)
+}
+
+fn sanitize(label: &str) -> String {
+    // every function is prefixed, so labels starting with a digit or named like a keyword stay valid
+    let name: String = label
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("rule_{name}")
+}
@@ -0,0 +1,310 @@
+use super::identifiers;
+use crate::analysis::first::{first_sets, left_recursive_rules, overlap};
+use crate::bnf::element::Element;
+use crate::bnf::grammar::Grammar;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Write};
+
+// what tree-sitter will complain about, or needs to be told, when generating the parser
+#[derive(Debug, Clone)]
+pub enum Warning {
+    // fine for tree-sitter, but an ambiguous one (<e> ::= <e> "+" <e>) needs prec.left or prec.right
+    LeftRecursion { rule: String },
+    // alternatives starting with these rules can start with the same character, tree-sitter may ask
+    // for them to be declared as a conflict, which they are in the generated file
+    Conflict { rules: Vec<String> },
+    // only the start rule may match the empty string, and this one still does after its ε alternative
+    // was moved to the places using it
+    MatchesEmpty { rule: String },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Warning::LeftRecursion { rule } => write!(
+                f,
+                "<{rule}> is left recursive, it needs a precedence if tree-sitter reports it as ambiguous"
+            ),
+            Warning::Conflict { rules } => {
+                let rules: Vec<String> = rules.iter().map(|rule| format!("<{rule}>")).collect();
+                write!(
+                    f,
+                    "{} can start alike, they are declared as a conflict",
+                    rules.join(" and ")
+                )
+            }
+            Warning::MatchesEmpty { rule } => write!(
+                f,
+                "<{rule}> matches the empty string, tree-sitter only allows it for the start rule"
+            ),
+        }
+    }
+}
+
+// how the references to a rule are written, tree-sitter refusing rules matching the empty string
+struct Emptiness {
+    // the rules only matching the empty string, left out of the file
+    empty: HashSet<usize>,
+    // the rules with an ε alternative, which is written as optional(...) around their references instead
+    optional: HashSet<usize>,
+}
+
+// writes the grammar as the grammar.js of a tree-sitter parser, the entrypoint being the start rule
+pub fn write_tree_sitter(grammar: &Grammar, name: &str, output: &mut dyn Write) -> fmt::Result {
+    let names = identifiers(grammar, sanitize, true);
+    let mut emptiness = emptiness(grammar);
+    let start = grammar.entrypoint();
+    // the start rule is allowed to match the empty string, so its ε alternative is written in its
+    // definition rather than around its references
+    emptiness.optional.remove(&start);
+
+    for warning in tree_sitter_warnings(grammar) {
+        writeln!(output, "// warning: {warning}")?;
+    }
+    writeln!(output, "module.exports = grammar({{")?;
+    writeln!(output, "  name: '{}',", sanitize(name))?;
+    // the whitespace is part of the BNF rules, tree-sitter mustn't skip any
+    writeln!(output, "  extras: $ => [],")?;
+    let conflicts = conflicts(grammar);
+    if !conflicts.is_empty() {
+        writeln!(output, "  conflicts: $ => [")?;
+        for conflict in conflicts.iter() {
+            let rules: Vec<String> = conflict
+                .iter()
+                .map(|label_index| format!("$.{}", names[label_index]))
+                .collect();
+            writeln!(output, "    [{}],", rules.join(", "))?;
+        }
+        writeln!(output, "  ],")?;
+    }
+    writeln!(output, "  rules: {{")?;
+    for label_index in grammar.labels.iter() {
+        if *label_index != start && emptiness.empty.contains(label_index) {
+            continue;
+        }
+        let mut alternatives = Vec::new();
+        let mut optional = false;
+        for sequence in grammar.maps[label_index].sequences.iter() {
+            let elements: Vec<String> = sequence
+                .elements
+                .iter()
+                .filter_map(|element| write_element(element, &names, &emptiness))
+                .collect();
+            match elements.len() {
+                0 => optional = true,
+                1 => alternatives.extend(elements),
+                _ => alternatives.push(format!("seq({})", elements.join(", "))),
+            }
+        }
+        let body = match alternatives.len() {
+            0 => "blank()".to_string(),
+            1 => alternatives[0].clone(),
+            _ => format!("choice({})", alternatives.join(", ")),
+        };
+        // the other rules get their ε alternative written around their references
+        let body = if optional && *label_index == start && !alternatives.is_empty() {
+            format!("optional({body})")
+        } else {
+            body
+        };
+        writeln!(output, "    {}: $ => {body},", names[label_index])?;
+    }
+    writeln!(output, "  }},")?;
+    writeln!(output, "}});")
+}
+
+pub fn tree_sitter_warnings(grammar: &Grammar) -> Vec<Warning> {
+    let label = |label_index: &usize| grammar.references[label_index].clone();
+    let mut warnings: Vec<Warning> = left_recursive_rules(grammar)
+        .iter()
+        .map(|label_index| Warning::LeftRecursion {
+            rule: label(label_index),
+        })
+        .collect();
+    for conflict in conflicts(grammar) {
+        warnings.push(Warning::Conflict {
+            rules: conflict.iter().map(label).collect(),
+        });
+    }
+
+    // a rule still matches the empty string when an alternative other than ε only holds nullable rules
+    let nullable = grammar.nullable_labels();
+    let emptiness = emptiness(grammar);
+    for label_index in grammar.labels.iter() {
+        if *label_index == grammar.entrypoint() || emptiness.empty.contains(label_index) {
+            continue;
+        }
+        let matches_empty = grammar.maps[label_index].sequences.iter().any(|sequence| {
+            let elements: Vec<&Element> = sequence
+                .elements
+                .iter()
+                .filter(|element| match element {
+                    Element::Empty => false,
+                    Element::Reference(target) => !emptiness.empty.contains(target),
+                    _ => true,
+                })
+                .collect();
+            !elements.is_empty()
+                && elements.iter().all(
+                    |element| matches!(element, Element::Reference(target) if nullable.contains(target)),
+                )
+        });
+        if matches_empty {
+            warnings.push(Warning::MatchesEmpty {
+                rule: label(label_index),
+            });
+        }
+    }
+    warnings
+}
+
+fn emptiness(grammar: &Grammar) -> Emptiness {
+    let only_empty = |elements: &[Element], empty: &HashSet<usize>| {
+        elements.iter().all(|element| match element {
+            Element::Empty => true,
+            Element::Literal(literal) => literal.is_empty(),
+            Element::Reference(target) => empty.contains(target),
+            Element::Range { .. } => false,
+        })
+    };
+
+    let mut empty = HashSet::new();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for label_index in grammar.labels.iter() {
+            if !empty.contains(label_index)
+                && grammar.maps[label_index]
+                    .sequences
+                    .iter()
+                    .all(|sequence| only_empty(&sequence.elements, &empty))
+            {
+                empty.insert(*label_index);
+                changed = true;
+            }
+        }
+    }
+    let optional = grammar
+        .labels
+        .iter()
+        .copied()
+        .filter(|label_index| {
+            !empty.contains(label_index)
+                && grammar.maps[label_index]
+                    .sequences
+                    .iter()
+                    .any(|sequence| only_empty(&sequence.elements, &empty))
+        })
+        .collect();
+    Emptiness { empty, optional }
+}
+
+// pairs of rules starting different alternatives of a rule, which can start with the same character
+fn conflicts(grammar: &Grammar) -> Vec<Vec<usize>> {
+    let nullable = grammar.nullable_labels();
+    let first = first_sets(grammar, &nullable);
+    let position: HashMap<usize, usize> = grammar
+        .labels
+        .iter()
+        .enumerate()
+        .map(|(i, label_index)| (*label_index, i))
+        .collect();
+    let mut conflicts: Vec<Vec<usize>> = Vec::new();
+    for label_index in grammar.labels.iter() {
+        let leading: Vec<usize> = grammar.maps[label_index]
+            .sequences
+            .iter()
+            .filter_map(|sequence| {
+                match sequence
+                    .elements
+                    .iter()
+                    .find(|element| !matches!(element, Element::Empty))
+                {
+                    Some(Element::Reference(target)) => Some(*target),
+                    _ => None,
+                }
+            })
+            .collect();
+        for (i, a) in leading.iter().enumerate() {
+            for b in leading[i + 1..].iter() {
+                // a left recursive alternative is already reported as left recursion
+                if a == b || a == label_index || b == label_index || !overlap(&first[a], &first[b])
+                {
+                    continue;
+                }
+                // in order of definition, so the same pair is only declared once
+                let mut pair = vec![*a, *b];
+                pair.sort_by_key(|target| position[target]);
+                if !conflicts.contains(&pair) {
+                    conflicts.push(pair);
+                }
+            }
+        }
+    }
+    conflicts
+}
+
+fn write_element(
+    element: &Element,
+    names: &HashMap<usize, String>,
+    emptiness: &Emptiness,
+) -> Option<String> {
+    match element {
+        Element::Empty => None,
+        Element::Literal(literal) if literal.is_empty() => None,
+        Element::Literal(literal) => Some(format!("'{}'", escape_string(literal))),
+        Element::Range { start, end } => Some(format!(
+            "/[{}-{}]/{}",
+            escape_class(*start),
+            escape_class(*end),
+            // without it, javascript reads a character outside the BMP as two UTF-16 code units
+            if *end as u32 > 0xFFFF { "u" } else { "" }
+        )),
+        Element::Reference(target) if emptiness.empty.contains(target) => None,
+        Element::Reference(target) if emptiness.optional.contains(target) => {
+            Some(format!("optional($.{})", names[target]))
+        }
+        Element::Reference(target) => Some(format!("$.{}", names[target])),
+    }
+}
+
+fn escape_string(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\'' => escaped.push_str("\\'"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04X}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn escape_class(c: char) -> String {
+    // letters and digits are written as themselves, the other characters by their code point,
+    // which is understood both by javascript and by the regex engine of tree-sitter, the braces of
+    // the characters outside the BMP needing the u flag in javascript
+    match c as u32 {
+        _ if c.is_ascii_alphanumeric() => c.to_string(),
+        code if code <= 0xFF => format!("\\x{code:02X}"),
+        code if code <= 0xFFFF => format!("\\u{code:04X}"),
+        code => format!("\\u{{{code:X}}}"),
+    }
+}
+
+fn sanitize(label: &str) -> String {
+    // rule names are javascript identifiers; a leading underscore would hide the rule from the syntax tree
+    let name: String = label
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        name
+    } else {
+        format!("rule{name}")
+    }
+}
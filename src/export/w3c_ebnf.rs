@@ -0,0 +1,85 @@
+use super::{identifiers, quoted_pieces, write_body, Notation};
+use crate::bnf::element::Element;
+use crate::bnf::grammar::Grammar;
+use std::fmt::Write;
+
+const NOTATION: Notation = Notation {
+    alternation: " | ",
+    concatenation: " ",
+    optional_start: "(",
+    optional_end: ")?",
+    empty: "''",
+};
+
+// writes the grammar in the EBNF notation of the W3C XML specification
+pub fn write_w3c_ebnf(grammar: &Grammar, output: &mut dyn Write) -> std::fmt::Result {
+    let names = identifiers(grammar, sanitize, true);
+    for label_index in grammar.labels.iter() {
+        write!(output, "{} ::= ", names[label_index])?;
+        write_body(
+            output,
+            grammar,
+            *label_index,
+            &NOTATION,
+            &|output: &mut dyn Write, element: &Element| match element {
+                Element::Empty => write!(output, "''"),
+                Element::Literal(literal) => write_literal(output, literal),
+                Element::Range { start, end } => {
+                    write!(output, "[{}-{}]", class_char(*start), class_char(*end))
+                }
+                Element::Reference(target) => write!(output, "{}", names[target]),
+            },
+        )?;
+        writeln!(output)?;
+    }
+    Ok(())
+}
+
+fn write_literal(output: &mut dyn Write, literal: &str) -> std::fmt::Result {
+    // control characters can't be written in a string, they use the #xN notation instead
+    let mut pieces = Vec::new();
+    let mut printable = String::new();
+    for c in literal.chars() {
+        if c.is_control() {
+            if !printable.is_empty() {
+                pieces.extend(quoted_pieces(&printable));
+                printable.clear();
+            }
+            pieces.push(format!("#x{:X}", c as u32));
+        } else {
+            printable.push(c);
+        }
+    }
+    if !printable.is_empty() {
+        pieces.extend(quoted_pieces(&printable));
+    }
+    write!(output, "{}", pieces.join(" "))
+}
+
+fn class_char(c: char) -> String {
+    // letters and digits read better as themselves, anything else could have a meaning in a class
+    if c.is_ascii_alphanumeric() {
+        c.to_string()
+    } else {
+        format!("#x{:X}", c as u32)
+    }
+}
+
+fn sanitize(label: &str) -> String {
+    // names start with a letter or an underscore, and go on with letters, digits, dashes, dots and underscores
+    let name: String = label
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        name
+    } else {
+        format!("_{name}")
+    }
+}
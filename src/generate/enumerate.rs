@@ -0,0 +1,125 @@
+use crate::bnf::element::Element;
+use crate::bnf::grammar::Grammar;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    // the longest string listed, in characters
+    pub max_length: usize,
+    // when set, a range only contributes this many evenly spaced characters (always including both ends),
+    // otherwise every character of the range is used
+    pub range_samples: Option<usize>,
+    // a rule matching more strings than this stops the enumeration
+    pub max_strings: usize,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            max_length: 4,
+            range_samples: None,
+            max_strings: 1_000_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Error {
+    // the rule matches too many strings, a smaller length or range samples are needed
+    TooManyStrings(String),
+}
+
+impl Grammar {
+    // every string accepted by the grammar up to the max length, in shortlex order
+    pub fn enumerate(&self, options: &Options) -> Result<Vec<String>, Error> {
+        // the strings of every rule grow at each round, until a fixed point is reached,
+        // which takes care of ε and of recursive rules
+        let mut languages: HashMap<usize, HashSet<String>> = self
+            .labels
+            .iter()
+            .map(|&label_index| (label_index, HashSet::new()))
+            .collect();
+        loop {
+            let mut changed = false;
+            for label_index in self.labels.iter() {
+                let mut language = HashSet::new();
+                for sequence in self.maps[label_index].sequences.iter() {
+                    let mut prefixes: HashSet<String> = HashSet::from([String::new()]);
+                    for element in sequence.elements.iter() {
+                        let suffixes = match element {
+                            Element::Empty => HashSet::from([String::new()]),
+                            Element::Literal(literal) => HashSet::from([literal.clone()]),
+                            Element::Range { start, end } => {
+                                range_chars(*start, *end, options.range_samples)
+                                    .into_iter()
+                                    .map(String::from)
+                                    .collect()
+                            }
+                            Element::Reference(target) => languages[target].clone(),
+                        };
+                        prefixes = concatenate(&prefixes, &suffixes, options.max_length);
+                        if prefixes.is_empty() {
+                            break;
+                        }
+                    }
+                    language.extend(prefixes);
+                }
+                if language.len() > options.max_strings {
+                    return Err(Error::TooManyStrings(self.references[label_index].clone()));
+                }
+                if language.len() != languages[label_index].len() {
+                    languages.insert(*label_index, language);
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        let mut strings: Vec<String> = languages
+            .remove(&self.entrypoint())
+            .expect("Entrypoint should exist.")
+            .into_iter()
+            .collect();
+        strings.sort_by(|a, b| a.chars().count().cmp(&b.chars().count()).then(a.cmp(b)));
+        Ok(strings)
+    }
+}
+
+fn concatenate(
+    prefixes: &HashSet<String>,
+    suffixes: &HashSet<String>,
+    max_length: usize,
+) -> HashSet<String> {
+    let mut strings = HashSet::new();
+    for prefix in prefixes.iter() {
+        let prefix_length = prefix.chars().count();
+        for suffix in suffixes.iter() {
+            if prefix_length + suffix.chars().count() <= max_length {
+                strings.insert(format!("{prefix}{suffix}"));
+            }
+        }
+    }
+    strings
+}
+
+fn range_chars(start: char, end: char, samples: Option<usize>) -> Vec<char> {
+    let (start, end) = (start as u32, end as u32);
+    if start > end {
+        return Vec::new();
+    }
+    let size = end - start + 1;
+    let codes: Vec<u32> = match samples {
+        Some(samples) if (samples as u32) < size => {
+            // evenly spaced, the first and the last one being the ends of the range
+            let samples = samples.max(2) as u64;
+            (0..samples)
+                .map(|i| start + ((size as u64 - 1) * i / (samples - 1)) as u32)
+                .collect()
+        }
+        _ => (start..=end).collect(),
+    };
+    // the surrogates aren't valid characters
+    codes.into_iter().filter_map(char::from_u32).collect()
+}
@@ -0,0 +1,2 @@
+pub mod enumerate;
+pub mod sample;
@@ -0,0 +1,134 @@
+use crate::bnf::element::Element;
+use crate::bnf::grammar::Grammar;
+use crate::bnf::sequence::Sequence;
+use crate::random::Random;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone)]
+pub enum Error {
+    // the entrypoint can't produce any string, every alternative ends up recursing forever
+    NoTerminatingDerivation(String),
+}
+
+pub struct Generator<'a> {
+    grammar: &'a Grammar,
+    // how many references deep a rule needs to go at least before only terminals are left
+    heights: HashMap<usize, usize>,
+    random: Random,
+    max_depth: usize,
+}
+
+impl<'a> Generator<'a> {
+    pub fn new(grammar: &'a Grammar, seed: u64, max_depth: usize) -> Result<Generator<'a>, Error> {
+        let heights = heights(grammar);
+        let entrypoint = grammar.entrypoint();
+        if !heights.contains_key(&entrypoint) {
+            return Err(Error::NoTerminatingDerivation(
+                grammar.references[&entrypoint].clone(),
+            ));
+        }
+        Ok(Generator {
+            grammar,
+            heights,
+            random: Random::new(seed),
+            max_depth,
+        })
+    }
+
+    pub fn generate(&mut self) -> String {
+        let mut output = String::new();
+
+        // the elements left to expand, with the depth they were found at, the next one on top
+        let mut stack: Vec<(&Element, usize)> = Vec::new();
+        let entrypoint = self.choose(self.grammar.entrypoint(), 0);
+        stack.extend(entrypoint.elements.iter().rev().map(|element| (element, 1)));
+
+        while let Some((element, depth)) = stack.pop() {
+            match element {
+                Element::Empty => {}
+                Element::Literal(literal) => output.push_str(literal),
+                Element::Range { start, end } => output.push(self.random_char(*start, *end)),
+                Element::Reference(label_index) => {
+                    let sequence = self.choose(*label_index, depth);
+                    stack.extend(
+                        sequence
+                            .elements
+                            .iter()
+                            .rev()
+                            .map(|element| (element, depth + 1)),
+                    );
+                }
+            }
+        }
+
+        output
+    }
+
+    fn choose(&mut self, label_index: usize, depth: usize) -> &'a Sequence {
+        let sequences = &self.grammar.maps[&label_index].sequences;
+        let heights: Vec<Option<usize>> = sequences
+            .iter()
+            .map(|sequence| sequence_height(sequence, &self.heights))
+            .collect();
+
+        // below the max depth any alternative that can terminate is fine,
+        // past it only the shortest ones are, so the remaining height keeps decreasing
+        let candidates: Vec<usize> = if depth < self.max_depth {
+            (0..sequences.len())
+                .filter(|&i| heights[i].is_some())
+                .collect()
+        } else {
+            let shortest = heights.iter().flatten().min().copied();
+            (0..sequences.len())
+                .filter(|&i| heights[i].is_some() && heights[i] == shortest)
+                .collect()
+        };
+        &sequences[candidates[self.random.below(candidates.len())]]
+    }
+
+    fn random_char(&mut self, start: char, end: char) -> char {
+        let (start, end) = (start as u32, end as u32);
+        // a surrogate isn't a valid char, the start of the range is used instead
+        let code = start + self.random.below((end - start + 1) as usize) as u32;
+        char::from_u32(code)
+            .unwrap_or(char::from_u32(start).expect("Start should be a valid char."))
+    }
+}
+
+fn sequence_height(sequence: &Sequence, heights: &HashMap<usize, usize>) -> Option<usize> {
+    // a reversed range never matches, so the sequence can't produce anything
+    let mut height = 0;
+    for element in sequence.elements.iter() {
+        match element {
+            Element::Reference(label_index) => height = height.max(*heights.get(label_index)?),
+            Element::Range { start, end } if start > end => return None,
+            Element::Empty | Element::Range { .. } | Element::Literal(_) => {}
+        }
+    }
+    Some(height)
+}
+
+fn heights(grammar: &Grammar) -> HashMap<usize, usize> {
+    // iterate until a fixed point, the rules missing at the end can't terminate
+    let mut heights: HashMap<usize, usize> = HashMap::new();
+    loop {
+        let mut changed = false;
+        for label_index in grammar.labels.iter() {
+            let height = grammar.maps[label_index]
+                .sequences
+                .iter()
+                .filter_map(|sequence| sequence_height(sequence, &heights))
+                .min()
+                .map(|height| height + 1);
+            if let Some(height) = height {
+                if heights.get(label_index) != Some(&height) {
+                    heights.insert(*label_index, height);
+                    changed = true;
+                }
+            }
+        }
+        if !changed {
+            return heights;
+        }
+    }
+}
@@ -0,0 +1,424 @@
+use super::{collect_references, Builder, Expression};
+use crate::bnf::grammar::Grammar;
+use std::collections::HashMap;
+
+// the core rules of RFC 5234 appendix B.1, added when the grammar uses them without defining them
+const CORE_RULES: &str = "ALPHA = %x41-5A / %x61-7A
+BIT = \"0\" / \"1\"
+CHAR = %x01-7F
+CR = %x0D
+CRLF = CR LF
+CTL = %x00-1F / %x7F
+DIGIT = %x30-39
+DQUOTE = %x22
+HEXDIG = DIGIT / \"A\" / \"B\" / \"C\" / \"D\" / \"E\" / \"F\"
+HTAB = %x09
+LF = %x0A
+LWSP = *(WSP / CRLF WSP)
+OCTET = %x00-FF
+SP = %x20
+VCHAR = %x21-7E
+WSP = SP / HTAB
+";
+
+#[derive(Debug, Clone)]
+pub enum Error {
+    // the rule starting on this line (counted from 1) isn't valid ABNF
+    Syntax { line: usize },
+    // prose values (<...>) describe a rule in plain words, which can't be turned into BNF
+    Prose { line: usize },
+    UndefinedRule(String),
+    DuplicateRule(String),
+    // =/ adds alternatives to a rule, which must have been defined before
+    IncrementOfUndefinedRule(String),
+    NoRules,
+}
+
+struct Rule {
+    name: String,
+    incremental: bool,
+    expression: Expression,
+}
+
+pub fn parse_abnf(source: &str) -> Result<Grammar, Error> {
+    let rules = parse_rules(source)?;
+    let core_rules = parse_rules(CORE_RULES)?;
+
+    // rule names are case insensitive, the spelling of the definition is kept
+    let mut builder = Builder::new();
+    let mut labels: HashMap<String, usize> = HashMap::new();
+    for rule in rules.iter() {
+        let key = rule.name.to_lowercase();
+        match (labels.contains_key(&key), rule.incremental) {
+            (false, false) => {
+                labels.insert(key, builder.declare(&rule.name));
+            }
+            (true, false) => return Err(Error::DuplicateRule(rule.name.clone())),
+            (false, true) => return Err(Error::IncrementOfUndefinedRule(rule.name.clone())),
+            (true, true) => {}
+        }
+    }
+    if labels.is_empty() {
+        return Err(Error::NoRules);
+    }
+
+    // pull the core rules used by the grammar, and the ones they use themselves
+    let mut used: Vec<&Rule> = rules.iter().collect();
+    let mut i = 0;
+    while i < used.len() {
+        let mut names = Vec::new();
+        collect_references(&used[i].expression, &mut names);
+        for name in names {
+            let key = name.to_lowercase();
+            let label_index = match labels.get(&key) {
+                Some(&label_index) => label_index,
+                None => {
+                    let Some(core) = core_rules
+                        .iter()
+                        .find(|core| core.name.to_lowercase() == key)
+                    else {
+                        return Err(Error::UndefinedRule(name));
+                    };
+                    let label_index = builder.declare(&core.name);
+                    labels.insert(key, label_index);
+                    used.push(core);
+                    label_index
+                }
+            };
+            // the references keep their own spelling
+            labels.insert(name, label_index);
+        }
+        i += 1;
+    }
+
+    for rule in used {
+        let label_index = labels[&rule.name.to_lowercase()];
+        builder.define(label_index, &rule.expression, &labels);
+    }
+    Ok(builder.grammar)
+}
+
+fn parse_rules(source: &str) -> Result<Vec<Rule>, Error> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut rules = Vec::new();
+    let mut index = 0;
+    while index < chars.len() {
+        let line = chars[..index].iter().filter(|&&c| c == '\n').count() + 1;
+
+        // skip the empty lines and the lines only holding a comment
+        let blank_end = skip_blanks(&chars, index);
+        if let Ok(new_index) = parse_comment_or_newline(&chars, blank_end) {
+            index = new_index;
+            continue;
+        }
+        match parse_rule(&chars, index) {
+            Ok((new_index, rule)) => {
+                rules.push(rule);
+                index = new_index;
+            }
+            Err(()) => {
+                // tell prose values apart, they are valid ABNF we just can't use
+                if has_prose(&chars, index) {
+                    return Err(Error::Prose { line });
+                }
+                return Err(Error::Syntax { line });
+            }
+        }
+    }
+    Ok(rules)
+}
+
+// whether a < starts an element of the rule, and not a quoted string or a comment, the rule going on
+// over the next lines starting with whitespace
+fn has_prose(chars: &[char], mut index: usize) -> bool {
+    let mut quoted = false;
+    while let Some(&c) = chars.get(index) {
+        match c {
+            '"' => quoted = !quoted,
+            '<' if !quoted => return true,
+            ';' if !quoted => {
+                while !matches!(chars.get(index), None | Some('\n')) {
+                    index += 1;
+                }
+                continue;
+            }
+            '\n' if !matches!(chars.get(index + 1), Some(' ' | '\t')) => return false,
+            // a quoted string can't go past the end of its line
+            '\n' => quoted = false,
+            _ => {}
+        }
+        index += 1;
+    }
+    false
+}
+
+fn parse_rule(chars: &[char], index: usize) -> Result<(usize, Rule), ()> {
+    let (index, name) = parse_rulename(chars, index)?;
+    let index = skip_c_wsp(chars, index);
+    let (index, incremental) = match (chars.get(index), chars.get(index + 1)) {
+        (Some('='), Some('/')) => (index + 2, true),
+        (Some('='), _) => (index + 1, false),
+        _ => return Err(()),
+    };
+    let index = skip_c_wsp(chars, index);
+    let (index, expression) = parse_alternation(chars, index)?;
+    let index = skip_c_wsp(chars, index);
+    let index = skip_blanks(chars, index);
+    let index = parse_comment_or_newline(chars, index)?;
+    Ok((
+        index,
+        Rule {
+            name,
+            incremental,
+            expression,
+        },
+    ))
+}
+
+fn parse_rulename(chars: &[char], index: usize) -> Result<(usize, String), ()> {
+    let Some(c) = chars.get(index).filter(|c| c.is_ascii_alphabetic()) else {
+        return Err(());
+    };
+    let mut name = c.to_string();
+    let mut index = index + 1;
+    while let Some(&c) = chars
+        .get(index)
+        .filter(|c| c.is_ascii_alphanumeric() || **c == '-')
+    {
+        name.push(c);
+        index += 1;
+    }
+    Ok((index, name))
+}
+
+fn skip_blanks(chars: &[char], mut index: usize) -> usize {
+    while let Some(' ' | '\t') = chars.get(index) {
+        index += 1;
+    }
+    index
+}
+
+fn parse_comment_or_newline(chars: &[char], mut index: usize) -> Result<usize, ()> {
+    if let Some(';') = chars.get(index) {
+        while let Some(&c) = chars.get(index) {
+            if c == '\r' || c == '\n' {
+                break;
+            }
+            index += 1;
+        }
+    }
+    match (chars.get(index), chars.get(index + 1)) {
+        (Some('\r'), Some('\n')) => Ok(index + 2),
+        (Some('\n'), _) => Ok(index + 1),
+        _ if index == chars.len() => Ok(index),
+        _ => Err(()),
+    }
+}
+
+fn skip_c_wsp(chars: &[char], mut index: usize) -> usize {
+    // whitespace, or a line break (maybe after a comment) followed by whitespace, which continues the rule
+    loop {
+        let blank_end = skip_blanks(chars, index);
+        if blank_end != index {
+            index = blank_end;
+            continue;
+        }
+        match parse_comment_or_newline(chars, index) {
+            Ok(new_index) if matches!(chars.get(new_index), Some(' ' | '\t')) => index = new_index,
+            _ => return index,
+        }
+    }
+}
+
+fn parse_alternation(chars: &[char], index: usize) -> Result<(usize, Expression), ()> {
+    let (mut index, first) = parse_concatenation(chars, index)?;
+    let mut expressions = vec![first];
+    loop {
+        let separator = skip_c_wsp(chars, index);
+        let Some('/') = chars.get(separator) else {
+            break;
+        };
+        let (new_index, expression) = parse_concatenation(chars, skip_c_wsp(chars, separator + 1))?;
+        expressions.push(expression);
+        index = new_index;
+    }
+    if expressions.len() == 1 {
+        Ok((
+            index,
+            expressions.pop().expect("There should be an expression."),
+        ))
+    } else {
+        Ok((index, Expression::Alternation(expressions)))
+    }
+}
+
+fn parse_concatenation(chars: &[char], index: usize) -> Result<(usize, Expression), ()> {
+    let (mut index, first) = parse_repetition(chars, index)?;
+    let mut expressions = vec![first];
+    loop {
+        let separator = skip_c_wsp(chars, index);
+        if separator == index {
+            break;
+        }
+        match parse_repetition(chars, separator) {
+            Ok((new_index, expression)) => {
+                expressions.push(expression);
+                index = new_index;
+            }
+            Err(()) => break,
+        }
+    }
+    if expressions.len() == 1 {
+        Ok((
+            index,
+            expressions.pop().expect("There should be an expression."),
+        ))
+    } else {
+        Ok((index, Expression::Concatenation(expressions)))
+    }
+}
+
+fn parse_repetition(chars: &[char], index: usize) -> Result<(usize, Expression), ()> {
+    // n*m, *m, n*, * or a plain n
+    let (index, min) = parse_decimal(chars, index);
+    let (index, min, max) = match chars.get(index) {
+        Some('*') => {
+            let (index, max) = parse_decimal(chars, index + 1);
+            (index, min.unwrap_or(0), max)
+        }
+        _ => match min {
+            Some(count) => (index, count, Some(count)),
+            None => {
+                return parse_element(chars, index);
+            }
+        },
+    };
+    let (index, expression) = parse_element(chars, index)?;
+    Ok((
+        index,
+        Expression::Repetition {
+            min,
+            max,
+            expression: Box::new(expression),
+        },
+    ))
+}
+
+fn parse_decimal(chars: &[char], mut index: usize) -> (usize, Option<usize>) {
+    let mut value: Option<usize> = None;
+    while let Some(digit) = chars.get(index).and_then(|c| c.to_digit(10)) {
+        value = Some(value.unwrap_or(0) * 10 + digit as usize);
+        index += 1;
+    }
+    (index, value)
+}
+
+fn parse_element(chars: &[char], index: usize) -> Result<(usize, Expression), ()> {
+    match chars.get(index) {
+        Some('(') | Some('[') => {
+            let close = if chars[index] == '(' { ')' } else { ']' };
+            let (index, expression) = parse_alternation(chars, skip_c_wsp(chars, index + 1))?;
+            let index = skip_c_wsp(chars, index);
+            if chars.get(index) != Some(&close) {
+                return Err(());
+            }
+            if close == ')' {
+                Ok((index + 1, expression))
+            } else {
+                Ok((
+                    index + 1,
+                    Expression::Repetition {
+                        min: 0,
+                        max: Some(1),
+                        expression: Box::new(expression),
+                    },
+                ))
+            }
+        }
+        Some('"') => {
+            let (index, text) = parse_quoted(chars, index)?;
+            if text.is_empty() {
+                return Ok((index, Expression::Empty));
+            }
+            Ok((index, Expression::CaseInsensitive(text)))
+        }
+        Some('%') => match chars.get(index + 1) {
+            // RFC 7405 case sensitive and explicitly case insensitive strings
+            Some('s') | Some('S') => {
+                let (index, text) = parse_quoted(chars, index + 2)?;
+                Ok((index, Expression::Literal(text)))
+            }
+            Some('i') | Some('I') => {
+                let (index, text) = parse_quoted(chars, index + 2)?;
+                Ok((index, Expression::CaseInsensitive(text)))
+            }
+            Some(base) => {
+                let radix = match base.to_ascii_lowercase() {
+                    'b' => 2,
+                    'd' => 10,
+                    'x' => 16,
+                    _ => return Err(()),
+                };
+                parse_numeric(chars, index + 2, radix)
+            }
+            None => Err(()),
+        },
+        Some(c) if c.is_ascii_alphabetic() => {
+            let (index, name) = parse_rulename(chars, index)?;
+            Ok((index, Expression::Reference(name)))
+        }
+        _ => Err(()),
+    }
+}
+
+fn parse_quoted(chars: &[char], index: usize) -> Result<(usize, String), ()> {
+    let Some('"') = chars.get(index) else {
+        return Err(());
+    };
+    let mut index = index + 1;
+    let mut text = String::new();
+    loop {
+        match chars.get(index) {
+            Some('"') => return Ok((index + 1, text)),
+            Some(&c) if (' '..='~').contains(&c) => text.push(c),
+            _ => return Err(()),
+        }
+        index += 1;
+    }
+}
+
+fn parse_numeric(chars: &[char], index: usize, radix: u32) -> Result<(usize, Expression), ()> {
+    let (mut index, first) = parse_code_point(chars, index, radix)?;
+
+    // a range: %x30-39
+    if let Some('-') = chars.get(index) {
+        let (index, last) = parse_code_point(chars, index + 1, radix)?;
+        return Ok((index, Expression::Range(first, last)));
+    }
+
+    // a concatenation of values: %x0D.0A
+    let mut text = first.to_string();
+    while let Some('.') = chars.get(index) {
+        let (new_index, c) = parse_code_point(chars, index + 1, radix)?;
+        text.push(c);
+        index = new_index;
+    }
+    Ok((index, Expression::Literal(text)))
+}
+
+fn parse_code_point(chars: &[char], mut index: usize, radix: u32) -> Result<(usize, char), ()> {
+    let start = index;
+    let mut value: u32 = 0;
+    while let Some(digit) = chars.get(index).and_then(|c| c.to_digit(radix)) {
+        value = value
+            .checked_mul(radix)
+            .ok_or(())?
+            .checked_add(digit)
+            .ok_or(())?;
+        index += 1;
+    }
+    if index == start {
+        return Err(());
+    }
+    Ok((index, char::from_u32(value).ok_or(())?))
+}
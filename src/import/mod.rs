@@ -0,0 +1,269 @@
+pub mod abnf;
+pub mod w3c_ebnf;
+
+use crate::bnf::alternation::Alternation;
+use crate::bnf::element::Element;
+use crate::bnf::grammar::Grammar;
+use crate::bnf::sequence::Sequence;
+use std::collections::HashMap;
+
+// the expressions of the imported notations, before they are turned into rules
+#[derive(Debug, Clone)]
+enum Expression {
+    Alternation(Vec<Expression>),
+    Concatenation(Vec<Expression>),
+    Repetition {
+        min: usize,
+        max: Option<usize>,
+        expression: Box<Expression>,
+    },
+    Reference(String),
+    Literal(String),
+    CaseInsensitive(String),
+    Range(char, char),
+    Empty,
+    // what the first expression matches except what the second one does, the importer has to resolve it
+    // to a set of characters before building
+    Exception {
+        expression: Box<Expression>,
+        excluded: Box<Expression>,
+        source: String,
+    },
+}
+
+// the names of the rules the expression references, in order of appearance
+fn collect_references(expression: &Expression, names: &mut Vec<String>) {
+    match expression {
+        Expression::Reference(name) => names.push(name.clone()),
+        Expression::Alternation(expressions) | Expression::Concatenation(expressions) => {
+            for expression in expressions.iter() {
+                collect_references(expression, names);
+            }
+        }
+        Expression::Repetition { expression, .. } => collect_references(expression, names),
+        Expression::Exception {
+            expression,
+            excluded,
+            ..
+        } => {
+            collect_references(expression, names);
+            collect_references(excluded, names);
+        }
+        Expression::Literal(_)
+        | Expression::CaseInsensitive(_)
+        | Expression::Range(..)
+        | Expression::Empty => {}
+    }
+}
+
+// builds the grammar, adding helper rules for the constructs BNF doesn't have (groups, options, repetitions)
+// right after the rule they come from
+struct Builder {
+    grammar: Grammar,
+    last_helper: HashMap<usize, usize>,
+    letters: HashMap<char, usize>,
+}
+
+impl Builder {
+    fn new() -> Builder {
+        Builder {
+            grammar: Grammar {
+                references: HashMap::new(),
+                labels: Vec::new(),
+                maps: HashMap::new(),
+            },
+            last_helper: HashMap::new(),
+            letters: HashMap::new(),
+        }
+    }
+
+    // the rule gets its body later, so rules can be referenced before being defined
+    fn declare(&mut self, label: &str) -> usize {
+        self.grammar.add_rule(
+            label.to_string(),
+            Alternation {
+                sequences: Vec::new(),
+            },
+        )
+    }
+
+    fn define(
+        &mut self,
+        label_index: usize,
+        expression: &Expression,
+        labels: &HashMap<String, usize>,
+    ) {
+        let sequences = self.alternatives(label_index, expression, labels);
+        self.grammar
+            .maps
+            .get_mut(&label_index)
+            .expect("Alternation should exist.")
+            .sequences
+            .extend(sequences);
+    }
+
+    fn alternatives(
+        &mut self,
+        rule: usize,
+        expression: &Expression,
+        labels: &HashMap<String, usize>,
+    ) -> Vec<Sequence> {
+        match expression {
+            Expression::Alternation(expressions) => expressions
+                .iter()
+                .map(|expression| Sequence::from_elements(self.elements(rule, expression, labels)))
+                .collect(),
+            expression => vec![Sequence::from_elements(
+                self.elements(rule, expression, labels),
+            )],
+        }
+    }
+
+    fn elements(
+        &mut self,
+        rule: usize,
+        expression: &Expression,
+        labels: &HashMap<String, usize>,
+    ) -> Vec<Element> {
+        match expression {
+            Expression::Empty => vec![Element::Empty],
+            Expression::Literal(literal) => vec![Element::Literal(literal.clone())],
+            Expression::Range(start, end) => vec![Element::Range {
+                start: *start,
+                end: *end,
+            }],
+            Expression::Reference(label) => vec![Element::Reference(labels[label])],
+            Expression::CaseInsensitive(literal) => self.case_insensitive(literal),
+            Expression::Exception { .. } => {
+                unreachable!("Exceptions should be resolved before building.")
+            }
+            Expression::Concatenation(expressions) => expressions
+                .iter()
+                .flat_map(|expression| self.elements(rule, expression, labels))
+                .collect(),
+            Expression::Alternation(expressions) if expressions.len() == 1 => {
+                self.elements(rule, &expressions[0], labels)
+            }
+            Expression::Alternation(_) => {
+                let sequences = self.alternatives(rule, expression, labels);
+                vec![Element::Reference(self.helper(rule, "group", sequences))]
+            }
+            Expression::Repetition {
+                min,
+                max,
+                expression,
+            } => {
+                let repeated = self.elements(rule, expression, labels);
+                let mut elements = Vec::new();
+                for _ in 0..*min {
+                    elements.extend(repeated.iter().cloned());
+                }
+                match max {
+                    // <rule-repeat> ::= x <rule-repeat> | ε
+                    None => {
+                        let repeat_index = self.helper(rule, "repeat", Vec::new());
+                        let mut sequence = repeated.clone();
+                        sequence.push(Element::Reference(repeat_index));
+                        self.grammar.maps.insert(
+                            repeat_index,
+                            Alternation {
+                                sequences: vec![
+                                    Sequence::from_elements(sequence),
+                                    Sequence::from_elements(Vec::new()),
+                                ],
+                            },
+                        );
+                        elements.push(Element::Reference(repeat_index));
+                    }
+                    // the optional occurrences are nested: <rule-option> ::= x <rule-option-2> | ε
+                    Some(max) if max > min => {
+                        let count = max - min;
+                        let indices: Vec<usize> = (0..count)
+                            .map(|_| self.helper(rule, "option", Vec::new()))
+                            .collect();
+                        for (i, option_index) in indices.iter().enumerate() {
+                            let mut sequence = repeated.clone();
+                            if let Some(next_index) = indices.get(i + 1) {
+                                sequence.push(Element::Reference(*next_index));
+                            }
+                            self.grammar.maps.insert(
+                                *option_index,
+                                Alternation {
+                                    sequences: vec![
+                                        Sequence::from_elements(sequence),
+                                        Sequence::from_elements(Vec::new()),
+                                    ],
+                                },
+                            );
+                        }
+                        elements.push(Element::Reference(indices[0]));
+                    }
+                    Some(_) => {}
+                }
+                if elements.is_empty() {
+                    vec![Element::Empty]
+                } else {
+                    elements
+                }
+            }
+        }
+    }
+
+    fn case_insensitive(&mut self, literal: &str) -> Vec<Element> {
+        // characters without case stay together in a literal, each letter references a rule matching both cases
+        let mut elements = Vec::new();
+        let mut run = String::new();
+        for c in literal.chars() {
+            let (lower, upper) = (c.to_ascii_lowercase(), c.to_ascii_uppercase());
+            if lower == upper {
+                run.push(c);
+                continue;
+            }
+            if !run.is_empty() {
+                elements.push(Element::Literal(run.clone()));
+                run.clear();
+            }
+            let letter_index = match self.letters.get(&lower) {
+                Some(&letter_index) => letter_index,
+                None => {
+                    let label = self.grammar.fresh_label(&format!("letter-{lower}"));
+                    let letter_index = self.grammar.add_rule(
+                        label,
+                        Alternation {
+                            sequences: vec![
+                                Sequence {
+                                    elements: vec![Element::Literal(lower.to_string())],
+                                },
+                                Sequence {
+                                    elements: vec![Element::Literal(upper.to_string())],
+                                },
+                            ],
+                        },
+                    );
+                    self.letters.insert(lower, letter_index);
+                    letter_index
+                }
+            };
+            elements.push(Element::Reference(letter_index));
+        }
+        if !run.is_empty() {
+            elements.push(Element::Literal(run));
+        }
+        if elements.is_empty() {
+            vec![Element::Empty]
+        } else {
+            elements
+        }
+    }
+
+    fn helper(&mut self, rule: usize, kind: &str, sequences: Vec<Sequence>) -> usize {
+        let base = format!("{}-{kind}", self.grammar.references[&rule]);
+        let label = self.grammar.fresh_label(&base);
+        let after = self.last_helper.get(&rule).copied().unwrap_or(rule);
+        let helper_index = self
+            .grammar
+            .add_rule_after(after, label, Alternation { sequences });
+        self.last_helper.insert(rule, helper_index);
+        helper_index
+    }
+}
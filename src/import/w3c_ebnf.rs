@@ -0,0 +1,515 @@
+use super::{collect_references, Builder, Expression};
+use crate::bnf::grammar::Grammar;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone)]
+pub enum Error {
+    // the rule starting on this line (counted from 1) isn't valid EBNF
+    Syntax { line: usize },
+    UndefinedRule(String),
+    DuplicateRule(String),
+    NoRules,
+    // every construct that has no equivalent in BNF
+    Unsupported(Vec<Unsupported>),
+}
+
+#[derive(Debug, Clone)]
+pub struct Unsupported {
+    pub rule: String,
+    pub construct: String,
+    pub reason: &'static str,
+}
+
+struct Rule {
+    name: String,
+    expression: Expression,
+}
+
+// reads the EBNF notation of the W3C specifications (XML, XPath, SPARQL...)
+pub fn parse_w3c_ebnf(source: &str) -> Result<Grammar, Error> {
+    let rules = parse_rules(source)?;
+    if rules.is_empty() {
+        return Err(Error::NoRules);
+    }
+
+    let mut builder = Builder::new();
+    let mut labels: HashMap<String, usize> = HashMap::new();
+    for rule in rules.iter() {
+        if labels.contains_key(&rule.name) {
+            return Err(Error::DuplicateRule(rule.name.clone()));
+        }
+        labels.insert(rule.name.clone(), builder.declare(&rule.name));
+    }
+    for rule in rules.iter() {
+        let mut names = Vec::new();
+        collect_references(&rule.expression, &mut names);
+        if let Some(name) = names.into_iter().find(|name| !labels.contains_key(name)) {
+            return Err(Error::UndefinedRule(name));
+        }
+    }
+
+    // exceptions only have an equivalent when both sides are sets of characters
+    let bodies: HashMap<&str, &Expression> = rules
+        .iter()
+        .map(|rule| (rule.name.as_str(), &rule.expression))
+        .collect();
+    let mut unsupported = Vec::new();
+    let expressions: Vec<Expression> = rules
+        .iter()
+        .map(|rule| resolve(&rule.expression, &rule.name, &bodies, &mut unsupported))
+        .collect();
+    if !unsupported.is_empty() {
+        return Err(Error::Unsupported(unsupported));
+    }
+
+    for (rule, expression) in rules.iter().zip(expressions.iter()) {
+        builder.define(labels[&rule.name], expression, &labels);
+    }
+    Ok(builder.grammar)
+}
+
+fn resolve(
+    expression: &Expression,
+    rule: &str,
+    bodies: &HashMap<&str, &Expression>,
+    unsupported: &mut Vec<Unsupported>,
+) -> Expression {
+    match expression {
+        Expression::Exception { source, .. } => {
+            let reason = match character_set(expression, bodies, &mut Vec::new()) {
+                Some(set) if set.is_empty() => "the exception leaves no character to match",
+                Some(set) => return set_expression(&set),
+                None => "only exceptions between sets of characters can be written in BNF",
+            };
+            unsupported.push(Unsupported {
+                rule: rule.to_string(),
+                construct: source.clone(),
+                reason,
+            });
+            Expression::Empty
+        }
+        Expression::Alternation(expressions) => Expression::Alternation(
+            expressions
+                .iter()
+                .map(|expression| resolve(expression, rule, bodies, unsupported))
+                .collect(),
+        ),
+        Expression::Concatenation(expressions) => Expression::Concatenation(
+            expressions
+                .iter()
+                .map(|expression| resolve(expression, rule, bodies, unsupported))
+                .collect(),
+        ),
+        Expression::Repetition {
+            min,
+            max,
+            expression,
+        } => Expression::Repetition {
+            min: *min,
+            max: *max,
+            expression: Box::new(resolve(expression, rule, bodies, unsupported)),
+        },
+        expression => expression.clone(),
+    }
+}
+
+// the characters matched by the expression, if it only ever matches a single character
+fn character_set(
+    expression: &Expression,
+    bodies: &HashMap<&str, &Expression>,
+    visiting: &mut Vec<String>,
+) -> Option<Vec<(char, char)>> {
+    match expression {
+        Expression::Literal(literal) if literal.chars().count() == 1 => {
+            let c = literal.chars().next()?;
+            Some(vec![(c, c)])
+        }
+        Expression::Range(start, end) => Some(vec![(*start, *end)]),
+        Expression::Alternation(expressions) => {
+            let mut set = Vec::new();
+            for expression in expressions.iter() {
+                set.extend(character_set(expression, bodies, visiting)?);
+            }
+            Some(normalize(set))
+        }
+        Expression::Reference(name) => {
+            if visiting.contains(name) {
+                return None;
+            }
+            visiting.push(name.clone());
+            let set = character_set(bodies[name.as_str()], bodies, visiting);
+            visiting.pop();
+            set
+        }
+        Expression::Exception {
+            expression,
+            excluded,
+            ..
+        } => {
+            let set = character_set(expression, bodies, visiting)?;
+            let excluded = character_set(excluded, bodies, visiting)?;
+            Some(difference(&set, &excluded))
+        }
+        _ => None,
+    }
+}
+
+fn set_expression(set: &[(char, char)]) -> Expression {
+    let mut expressions: Vec<Expression> = set
+        .iter()
+        .map(|&(start, end)| {
+            if start == end {
+                Expression::Literal(start.to_string())
+            } else {
+                Expression::Range(start, end)
+            }
+        })
+        .collect();
+    if expressions.len() == 1 {
+        expressions.pop().expect("There should be an expression.")
+    } else {
+        Expression::Alternation(expressions)
+    }
+}
+
+// sorts the intervals and merges the ones overlapping or touching
+fn normalize(mut set: Vec<(char, char)>) -> Vec<(char, char)> {
+    set.sort();
+    let mut merged: Vec<(char, char)> = Vec::new();
+    for (start, end) in set {
+        match merged.last_mut() {
+            Some(last) if next_char(last.1).is_none_or(|c| c >= start) => {
+                last.1 = last.1.max(end);
+            }
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+// the characters of the set which aren't in the excluded set, both being normalized
+fn difference(set: &[(char, char)], excluded: &[(char, char)]) -> Vec<(char, char)> {
+    let mut result = Vec::new();
+    for &(start, end) in set.iter() {
+        let mut current = Some(start);
+        for &(excluded_start, excluded_end) in excluded.iter() {
+            let Some(start) = current else {
+                break;
+            };
+            if excluded_end < start || excluded_start > end {
+                continue;
+            }
+            if excluded_start > start {
+                result.push((start, previous_char(excluded_start)));
+            }
+            current = next_char(excluded_end).filter(|&c| c <= end);
+        }
+        if let Some(start) = current {
+            result.push((start, end));
+        }
+    }
+    result
+}
+
+// chars skip the surrogates, so the neighbours of their bounds are on the other side of them
+fn next_char(c: char) -> Option<char> {
+    match c {
+        '\u{D7FF}' => Some('\u{E000}'),
+        c => char::from_u32(c as u32 + 1),
+    }
+}
+
+fn previous_char(c: char) -> char {
+    match c {
+        '\u{E000}' => '\u{D7FF}',
+        c => char::from_u32(c as u32 - 1).expect("There should be a previous character."),
+    }
+}
+
+fn parse_rules(source: &str) -> Result<Vec<Rule>, Error> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut rules = Vec::new();
+    let mut index = skip_space(&chars, 0);
+    while index < chars.len() {
+        let line = chars[..index].iter().filter(|&&c| c == '\n').count() + 1;
+        let Ok((new_index, rule)) = parse_rule(&chars, index) else {
+            return Err(Error::Syntax { line });
+        };
+        rules.push(rule);
+        index = skip_space(&chars, new_index);
+    }
+    Ok(rules)
+}
+
+fn parse_rule(chars: &[char], index: usize) -> Result<(usize, Rule), ()> {
+    let (index, name) = parse_rule_start(chars, index)?;
+    let (index, expression) = parse_alternation(chars, skip_space(chars, index))?;
+
+    // the rule goes on until the next one starts
+    let end = skip_space(chars, index);
+    if end < chars.len() && parse_rule_start(chars, end).is_err() {
+        return Err(());
+    }
+    Ok((index, Rule { name, expression }))
+}
+
+fn parse_rule_start(chars: &[char], mut index: usize) -> Result<(usize, String), ()> {
+    // the rules of the specifications are usually numbered: [1] document ::= ...
+    if let Some('[') = chars.get(index) {
+        let number_end = chars[index + 1..]
+            .iter()
+            .position(|c| !c.is_ascii_alphanumeric())
+            .map_or(chars.len(), |position| index + 1 + position);
+        if number_end == index + 1 || chars.get(number_end) != Some(&']') {
+            return Err(());
+        }
+        index = skip_space(chars, number_end + 1);
+    }
+    let (index, name) = parse_name(chars, index)?;
+    let index = skip_space(chars, index);
+    if chars[index..].starts_with(&[':', ':', '=']) {
+        Ok((index + 3, name))
+    } else {
+        Err(())
+    }
+}
+
+// names are XML names, so they can hold dashes and dots after their first character, an exception
+// being written with spaces around its dash
+fn parse_name(chars: &[char], index: usize) -> Result<(usize, String), ()> {
+    let Some(c) = chars.get(index).filter(|c| c.is_alphabetic() || **c == '_') else {
+        return Err(());
+    };
+    let mut name = c.to_string();
+    let mut index = index + 1;
+    while let Some(&c) = chars
+        .get(index)
+        .filter(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))
+    {
+        name.push(c);
+        index += 1;
+    }
+    Ok((index, name))
+}
+
+fn skip_space(chars: &[char], mut index: usize) -> usize {
+    // whitespace, /* comments */ and the [ wfc: ... ] or [ vc: ... ] constraints following some rules
+    loop {
+        match chars.get(index) {
+            Some(c) if c.is_whitespace() => index += 1,
+            Some('/') if chars.get(index + 1) == Some(&'*') => {
+                match (index + 2..chars.len().saturating_sub(1))
+                    .find(|&i| chars[i] == '*' && chars[i + 1] == '/')
+                {
+                    Some(end) => index = end + 2,
+                    None => return index,
+                }
+            }
+            Some('[') if is_constraint(chars, index + 1) => {
+                match chars[index..].iter().position(|&c| c == ']') {
+                    Some(position) => index += position + 1,
+                    None => return index,
+                }
+            }
+            _ => return index,
+        }
+    }
+}
+
+fn is_constraint(chars: &[char], mut index: usize) -> bool {
+    while let Some(' ' | '\t') = chars.get(index) {
+        index += 1;
+    }
+    chars[index.min(chars.len())..].starts_with(&['w', 'f', 'c', ':'])
+        || chars[index.min(chars.len())..].starts_with(&['v', 'c', ':'])
+}
+
+fn parse_alternation(chars: &[char], index: usize) -> Result<(usize, Expression), ()> {
+    let (mut index, first) = parse_concatenation(chars, index)?;
+    let mut expressions = vec![first];
+    loop {
+        let separator = skip_space(chars, index);
+        let Some('|') = chars.get(separator) else {
+            break;
+        };
+        let (new_index, expression) = parse_concatenation(chars, skip_space(chars, separator + 1))?;
+        expressions.push(expression);
+        index = new_index;
+    }
+    if expressions.len() == 1 {
+        Ok((
+            index,
+            expressions.pop().expect("There should be an expression."),
+        ))
+    } else {
+        Ok((index, Expression::Alternation(expressions)))
+    }
+}
+
+fn parse_concatenation(chars: &[char], index: usize) -> Result<(usize, Expression), ()> {
+    let (mut index, first) = parse_exception(chars, index)?;
+    let mut expressions = vec![first];
+    loop {
+        let next = skip_space(chars, index);
+        if next == chars.len() || parse_rule_start(chars, next).is_ok() {
+            break;
+        }
+        match parse_exception(chars, next) {
+            Ok((new_index, expression)) => {
+                expressions.push(expression);
+                index = new_index;
+            }
+            Err(()) => break,
+        }
+    }
+    if expressions.len() == 1 {
+        Ok((
+            index,
+            expressions.pop().expect("There should be an expression."),
+        ))
+    } else {
+        Ok((index, Expression::Concatenation(expressions)))
+    }
+}
+
+fn parse_exception(chars: &[char], start: usize) -> Result<(usize, Expression), ()> {
+    let (index, expression) = parse_postfix(chars, start)?;
+    let separator = skip_space(chars, index);
+    let Some('-') = chars.get(separator) else {
+        return Ok((index, expression));
+    };
+    let (index, excluded) = parse_postfix(chars, skip_space(chars, separator + 1))?;
+    Ok((
+        index,
+        Expression::Exception {
+            expression: Box::new(expression),
+            excluded: Box::new(excluded),
+            source: chars[start..index].iter().collect(),
+        },
+    ))
+}
+
+fn parse_postfix(chars: &[char], index: usize) -> Result<(usize, Expression), ()> {
+    let (index, expression) = parse_primary(chars, index)?;
+    let (min, max) = match chars.get(index) {
+        Some('?') => (0, Some(1)),
+        Some('*') => (0, None),
+        Some('+') => (1, None),
+        _ => return Ok((index, expression)),
+    };
+    Ok((
+        index + 1,
+        Expression::Repetition {
+            min,
+            max,
+            expression: Box::new(expression),
+        },
+    ))
+}
+
+fn parse_primary(chars: &[char], index: usize) -> Result<(usize, Expression), ()> {
+    match chars.get(index) {
+        Some('(') => {
+            let (index, expression) = parse_alternation(chars, skip_space(chars, index + 1))?;
+            let index = skip_space(chars, index);
+            if chars.get(index) != Some(&')') {
+                return Err(());
+            }
+            Ok((index + 1, expression))
+        }
+        Some(&quote) if quote == '\'' || quote == '"' => {
+            let length = chars[index + 1..]
+                .iter()
+                .position(|&c| c == quote)
+                .ok_or(())?;
+            let literal: String = chars[index + 1..index + 1 + length].iter().collect();
+            let expression = if literal.is_empty() {
+                Expression::Empty
+            } else {
+                Expression::Literal(literal)
+            };
+            Ok((index + length + 2, expression))
+        }
+        Some('#') => {
+            let (index, c) = parse_code_point(chars, index)?;
+            Ok((index, Expression::Literal(c.to_string())))
+        }
+        Some('[') => parse_class(chars, index),
+        _ => {
+            let (index, name) = parse_name(chars, index)?;
+            Ok((index, Expression::Reference(name)))
+        }
+    }
+}
+
+fn parse_code_point(chars: &[char], index: usize) -> Result<(usize, char), ()> {
+    if !chars[index..].starts_with(&['#', 'x']) {
+        return Err(());
+    }
+    let mut index = index + 2;
+    let start = index;
+    let mut value: u32 = 0;
+    while let Some(digit) = chars.get(index).and_then(|c| c.to_digit(16)) {
+        value = value
+            .checked_mul(16)
+            .ok_or(())?
+            .checked_add(digit)
+            .ok_or(())?;
+        index += 1;
+    }
+    if index == start {
+        return Err(());
+    }
+    Ok((index, char::from_u32(value).ok_or(())?))
+}
+
+fn parse_class(chars: &[char], index: usize) -> Result<(usize, Expression), ()> {
+    // [a-zA-Z], [#x20#x9], or the characters not listed with [^<&]
+    let mut index = index + 1;
+    let negated = chars.get(index) == Some(&'^');
+    if negated {
+        index += 1;
+    }
+    let mut set = Vec::new();
+    loop {
+        match chars.get(index) {
+            None => return Err(()),
+            Some(']') => break,
+            _ => {}
+        }
+        let (new_index, start) = parse_class_char(chars, index)?;
+        index = new_index;
+        // a dash right before the closing bracket is the character itself
+        if chars.get(index) == Some(&'-') && chars.get(index + 1) != Some(&']') {
+            let (new_index, end) = parse_class_char(chars, index + 1)?;
+            if end < start {
+                return Err(());
+            }
+            set.push((start, end));
+            index = new_index;
+        } else {
+            set.push((start, start));
+        }
+    }
+    if set.is_empty() {
+        return Err(());
+    }
+    let set = normalize(set);
+    let set = if negated {
+        difference(&[('\0', char::MAX)], &set)
+    } else {
+        set
+    };
+    if set.is_empty() {
+        return Err(());
+    }
+    Ok((index + 1, set_expression(&set)))
+}
+
+fn parse_class_char(chars: &[char], index: usize) -> Result<(usize, char), ()> {
+    match chars.get(index) {
+        Some('#') if chars.get(index + 1) == Some(&'x') => parse_code_point(chars, index),
+        Some(&c) => Ok((index + 1, c)),
+        None => Err(()),
+    }
+}
@@ -0,0 +1,18 @@
+use std::fmt::Write;
+
+// writes the string as a quoted JSON string
+pub fn write_string(output: &mut dyn Write, string: &str) -> std::fmt::Result {
+    write!(output, "\"")?;
+    for c in string.chars() {
+        match c {
+            '"' => write!(output, "\\\"")?,
+            '\\' => write!(output, "\\\\")?,
+            '\n' => write!(output, "\\n")?,
+            '\r' => write!(output, "\\r")?,
+            '\t' => write!(output, "\\t")?,
+            c if (c as u32) < 0x20 => write!(output, "\\u{:04x}", c as u32)?,
+            c => write!(output, "{c}")?,
+        }
+    }
+    write!(output, "\"")
+}
@@ -0,0 +1,12 @@
+// the parsers signal failure with `Err(())`, the position is the only information they carry
+#![allow(clippy::result_unit_err)]
+
+pub mod analysis;
+pub mod bnf;
+pub mod export;
+pub mod generate;
+pub mod import;
+pub mod json;
+pub mod random;
+pub mod transform;
+pub mod xml;
@@ -0,0 +1,98 @@
+use crate::bnf::alternation::Alternation;
+use crate::bnf::element::Element;
+use crate::bnf::grammar::Grammar;
+use crate::bnf::sequence::Sequence;
+use std::collections::{HashMap, VecDeque};
+
+impl Grammar {
+    pub fn left_factor(&self) -> Grammar {
+        let mut grammar = self.clone();
+
+        // helper rules are named after the rule they were extracted from in the original grammar
+        let mut roots: HashMap<usize, String> = grammar.references.clone();
+
+        let mut pending: VecDeque<usize> = grammar.labels.iter().copied().collect();
+        while let Some(label_index) = pending.pop_front() {
+            // keep the helper rules of a single rule next to each other
+            let mut insert_after = label_index;
+
+            while let Some((position, group)) = find_common_group(&grammar.maps[&label_index]) {
+                let sequences = &grammar.maps[&label_index].sequences;
+
+                // compute the longest prefix shared by the whole group
+                let mut prefix_length = 1;
+                while group.iter().all(|&i| {
+                    sequences[i].elements.len() > prefix_length
+                        && sequences[i].elements[prefix_length]
+                            == sequences[group[0]].elements[prefix_length]
+                }) {
+                    prefix_length += 1;
+                }
+                let prefix = Sequence {
+                    elements: sequences[group[0]].elements[..prefix_length].to_vec(),
+                };
+
+                // collect what is left after the prefix, without duplicates
+                let mut suffixes: Vec<Sequence> = Vec::new();
+                for &i in group.iter() {
+                    let suffix =
+                        Sequence::from_elements(sequences[i].elements[prefix_length..].to_vec());
+                    if !suffixes.contains(&suffix) {
+                        suffixes.push(suffix);
+                    }
+                }
+
+                // a single suffix means the whole group was the same sequence
+                let factored = if suffixes.len() == 1 {
+                    prefix.concat(&suffixes[0])
+                } else {
+                    let root = roots[&label_index].clone();
+                    let label = grammar.fresh_label(&format!("{root}-rest"));
+                    let new_index = grammar.add_rule_after(
+                        insert_after,
+                        label,
+                        Alternation {
+                            sequences: suffixes,
+                        },
+                    );
+                    roots.insert(new_index, root);
+                    pending.push_back(new_index);
+                    insert_after = new_index;
+                    prefix.concat(&Sequence {
+                        elements: vec![Element::Reference(new_index)],
+                    })
+                };
+
+                // replace the group by the factored sequence, at the place of its first member
+                let alternation = grammar
+                    .maps
+                    .get_mut(&label_index)
+                    .expect("Alternation should exist.");
+                alternation.sequences[position] = factored;
+                for &i in group.iter().skip(1).rev() {
+                    alternation.sequences.remove(i);
+                }
+            }
+        }
+
+        grammar
+    }
+}
+
+fn find_common_group(alternation: &Alternation) -> Option<(usize, Vec<usize>)> {
+    // find the first sequence whose first element is shared with a later sequence
+    let sequences = &alternation.sequences;
+    for (i, sequence) in sequences.iter().enumerate() {
+        let first = &sequence.elements[0];
+        if let Element::Empty = first {
+            continue;
+        }
+        let group: Vec<usize> = (i..sequences.len())
+            .filter(|&j| &sequences[j].elements[0] == first)
+            .collect();
+        if group.len() > 1 {
+            return Some((i, group));
+        }
+    }
+    None
+}
@@ -0,0 +1,146 @@
+use crate::analysis::first::{left_corners, reaches};
+use crate::bnf::alternation::Alternation;
+use crate::bnf::element::Element;
+use crate::bnf::grammar::Grammar;
+use crate::bnf::sequence::Sequence;
+use std::collections::HashSet;
+
+#[derive(Debug, Clone)]
+pub enum Error {
+    // every alternative of the rule starts with the rule itself, so it can never terminate
+    NoBaseCase(String),
+    // the rule is still left recursive through a prefix that can match the empty string
+    HiddenLeftRecursion(String),
+}
+
+impl Grammar {
+    pub fn eliminate_left_recursion(&self) -> Result<Grammar, Error> {
+        let mut grammar = self.clone();
+
+        // only the rules taking part in a left recursive cycle need to be rewritten
+        let order: Vec<usize> = {
+            let corners = left_corners(&grammar, &HashSet::new());
+            grammar
+                .labels
+                .iter()
+                .copied()
+                .filter(|label_index| reaches(&corners, *label_index, *label_index))
+                .collect()
+        };
+
+        for (i, &label_index) in order.iter().enumerate() {
+            // substitute the previous rules of the cycle when they start a sequence (indirect recursion)
+            for &previous_index in order[..i].iter() {
+                let corners = left_corners(&grammar, &HashSet::new());
+                if !reaches(&corners, previous_index, label_index) {
+                    continue;
+                }
+                let previous = grammar.maps[&previous_index].clone();
+                let alternation = grammar
+                    .maps
+                    .get_mut(&label_index)
+                    .expect("Alternation should exist.");
+                let mut sequences = Vec::new();
+                for sequence in alternation.sequences.iter() {
+                    match split_left_corner(sequence) {
+                        Some((corner, rest)) if corner == previous_index => {
+                            for replacement in previous.sequences.iter() {
+                                let substituted = replacement.concat(&rest);
+                                if !sequences.contains(&substituted) {
+                                    sequences.push(substituted);
+                                }
+                            }
+                        }
+                        _ => sequences.push(sequence.clone()),
+                    }
+                }
+                alternation.sequences = sequences;
+            }
+
+            // then remove the direct recursion
+            eliminate_direct(&mut grammar, label_index)?;
+        }
+
+        // recursion through nullable prefixes can't be removed this way, so we report it
+        let corners = left_corners(&grammar, &grammar.nullable_labels());
+        for label_index in grammar.labels.iter() {
+            if reaches(&corners, *label_index, *label_index) {
+                return Err(Error::HiddenLeftRecursion(
+                    grammar.references[label_index].clone(),
+                ));
+            }
+        }
+
+        Ok(grammar)
+    }
+}
+
+fn eliminate_direct(grammar: &mut Grammar, label_index: usize) -> Result<(), Error> {
+    // split the alternatives in "A ::= A alpha" and "A ::= beta"
+    let mut recursive = Vec::new();
+    let mut base = Vec::new();
+    for sequence in grammar.maps[&label_index].sequences.iter() {
+        match split_left_corner(sequence) {
+            Some((corner, rest)) if corner == label_index => {
+                // "A ::= A" doesn't add anything to the language
+                if rest.elements != [Element::Empty] {
+                    recursive.push(rest);
+                }
+            }
+            _ => base.push(sequence.clone()),
+        }
+    }
+    if recursive.is_empty() {
+        return Ok(());
+    }
+    if base.is_empty() {
+        return Err(Error::NoBaseCase(grammar.references[&label_index].clone()));
+    }
+
+    // A ::= beta <A-tail>
+    // <A-tail> ::= alpha <A-tail> | ε
+    let label = grammar.fresh_label(&format!("{}-tail", grammar.references[&label_index]));
+    let tail_index = grammar.add_rule_after(
+        label_index,
+        label,
+        Alternation {
+            sequences: Vec::new(),
+        },
+    );
+    let tail = Sequence {
+        elements: vec![Element::Reference(tail_index)],
+    };
+    let mut tail_sequences: Vec<Sequence> =
+        recursive.iter().map(|alpha| alpha.concat(&tail)).collect();
+    tail_sequences.push(Sequence {
+        elements: vec![Element::Empty],
+    });
+    grammar.maps.insert(
+        tail_index,
+        Alternation {
+            sequences: tail_sequences,
+        },
+    );
+    grammar.maps.insert(
+        label_index,
+        Alternation {
+            sequences: base.iter().map(|beta| beta.concat(&tail)).collect(),
+        },
+    );
+    Ok(())
+}
+
+fn split_left_corner(sequence: &Sequence) -> Option<(usize, Sequence)> {
+    // the left corner is the first element that isn't ε
+    let position = sequence
+        .elements
+        .iter()
+        .position(|element| !matches!(element, Element::Empty))?;
+    let Element::Reference(label_index) = sequence.elements[position] else {
+        return None;
+    };
+    Some((
+        label_index,
+        Sequence::from_elements(sequence.elements[position + 1..].to_vec()),
+    ))
+}
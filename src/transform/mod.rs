@@ -0,0 +1,4 @@
+pub mod left_factor;
+pub mod left_recursion;
+pub mod normal_form;
+pub mod simplify;
@@ -0,0 +1,445 @@
+use crate::bnf::alternation::Alternation;
+use crate::bnf::element::Element;
+use crate::bnf::grammar::Grammar;
+use crate::bnf::sequence::Sequence;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone)]
+pub struct NormalForm {
+    pub grammar: Grammar,
+    // the rule of the original grammar each rule of the new grammar was generated from,
+    // the terminal rules (<char-a>, <range-0-9>, ...) are shared between rules so they have none
+    pub origins: HashMap<usize, usize>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Error {
+    // the entrypoint doesn't match any string, so no rule would be left
+    EmptyLanguage,
+}
+
+impl Grammar {
+    // every rule is either <A> ::= <B> <C> or <A> ::= "a" (a single character or a range),
+    // and only the entrypoint can be ε
+    pub fn to_cnf(&self) -> Result<NormalForm, Error> {
+        let mut form = NormalForm {
+            grammar: self.clone(),
+            origins: self
+                .labels
+                .iter()
+                .map(|&label_index| (label_index, label_index))
+                .collect(),
+        };
+        form.add_start_rule();
+        form.split_literals();
+        form.isolate_terminals();
+        form.binarize();
+        form.remove_empty();
+        form.remove_units();
+        form.remove_useless()?;
+        Ok(form)
+    }
+
+    // every rule is <A> ::= "a" <B> <C> ..., with a single character or a range first and only references after,
+    // and only the entrypoint can be ε
+    pub fn to_gnf(&self) -> Result<NormalForm, Error> {
+        let mut form = self.to_cnf()?;
+
+        // put the ε of the entrypoint aside, nothing can reference the entrypoint anyway
+        let entrypoint = form.grammar.entrypoint();
+        let empty = Sequence {
+            elements: vec![Element::Empty],
+        };
+        let entry_sequences = form.sequences_mut(entrypoint);
+        let accepts_empty = entry_sequences.contains(&empty);
+        entry_sequences.retain(|sequence| sequence != &empty);
+
+        // make every rule start with a terminal or a rule defined after it
+        let order = form.grammar.labels.clone();
+        let position: HashMap<usize, usize> = order
+            .iter()
+            .enumerate()
+            .map(|(position, &label_index)| (label_index, position))
+            .collect();
+        let mut tails = Vec::new();
+        for (i, &label_index) in order.iter().enumerate() {
+            form.substitute_leading(label_index, |corner| {
+                position.get(&corner).is_some_and(|&j| j < i)
+            });
+            if let Some(tail_index) = form.eliminate_direct(label_index) {
+                tails.push(tail_index);
+            }
+        }
+
+        // the last rule can only start with terminals, so going backward every rule ends up starting with one
+        for &label_index in order.iter().rev() {
+            form.substitute_leading(label_index, |_| true);
+        }
+        for &tail_index in tails.iter() {
+            form.substitute_leading(tail_index, |_| true);
+        }
+
+        if accepts_empty {
+            form.sequences_mut(entrypoint).push(empty);
+        }
+
+        // the substitutions can leave rules nothing references anymore
+        form.remove_useless()?;
+        Ok(form)
+    }
+}
+
+impl NormalForm {
+    fn sequences_mut(&mut self, label_index: usize) -> &mut Vec<Sequence> {
+        &mut self
+            .grammar
+            .maps
+            .get_mut(&label_index)
+            .expect("Alternation should exist.")
+            .sequences
+    }
+
+    fn add_derived_rule(&mut self, after: usize, base: &str, sequences: Vec<Sequence>) -> usize {
+        let label = self.grammar.fresh_label(base);
+        let label_index = self
+            .grammar
+            .add_rule_after(after, label, Alternation { sequences });
+        if let Some(&origin) = self.origins.get(&after) {
+            self.origins.insert(label_index, origin);
+        }
+        label_index
+    }
+
+    fn add_start_rule(&mut self) {
+        // the entrypoint must not appear on the right side, otherwise its ε would leak into other rules
+        let entrypoint = self.grammar.entrypoint();
+        let referenced = self.grammar.maps.values().any(|alternation| {
+            alternation
+                .sequences
+                .iter()
+                .any(|sequence| sequence.elements.contains(&Element::Reference(entrypoint)))
+        });
+        if !referenced {
+            return;
+        }
+        let base = format!("{}-start", self.grammar.references[&entrypoint]);
+        let start_index = self.add_derived_rule(
+            entrypoint,
+            &base,
+            vec![Sequence {
+                elements: vec![Element::Reference(entrypoint)],
+            }],
+        );
+        self.grammar.set_entrypoint(start_index);
+    }
+
+    fn split_literals(&mut self) {
+        // a literal of several characters is the sequence of its characters
+        for alternation in self.grammar.maps.values_mut() {
+            for sequence in alternation.sequences.iter_mut() {
+                let mut elements = Vec::new();
+                for element in sequence.elements.drain(..) {
+                    match element {
+                        Element::Literal(literal) => elements
+                            .extend(literal.chars().map(|c| Element::Literal(c.to_string()))),
+                        element => elements.push(element),
+                    }
+                }
+                *sequence = Sequence::from_elements(elements);
+            }
+        }
+    }
+
+    fn isolate_terminals(&mut self) {
+        // terminals may only appear alone, so longer sequences reference a rule matching the terminal instead
+        let mut terminals: HashMap<Element, usize> = HashMap::new();
+        for label_index in self.grammar.labels.clone() {
+            let mut sequences = self.grammar.maps[&label_index].sequences.clone();
+            for sequence in sequences.iter_mut() {
+                if sequence.elements.len() < 2 {
+                    continue;
+                }
+                for element in sequence.elements.iter_mut() {
+                    if let Element::Reference(_) = element {
+                        continue;
+                    }
+                    let terminal_index = match terminals.get(element) {
+                        Some(&terminal_index) => terminal_index,
+                        None => {
+                            let label = self.grammar.fresh_label(&terminal_label(element));
+                            let terminal_index = self.grammar.add_rule(
+                                label,
+                                Alternation {
+                                    sequences: vec![Sequence {
+                                        elements: vec![element.clone()],
+                                    }],
+                                },
+                            );
+                            terminals.insert(element.clone(), terminal_index);
+                            terminal_index
+                        }
+                    };
+                    *element = Element::Reference(terminal_index);
+                }
+            }
+            *self.sequences_mut(label_index) = sequences;
+        }
+    }
+
+    fn binarize(&mut self) {
+        // <A> ::= <B> <C> <D> becomes <A> ::= <B> <A-part> and <A-part> ::= <C> <D>
+        for label_index in self.grammar.labels.clone() {
+            let mut sequences = self.grammar.maps[&label_index].sequences.clone();
+            let mut insert_after = label_index;
+            for sequence in sequences.iter_mut() {
+                if sequence.elements.len() <= 2 {
+                    continue;
+                }
+                let base = format!("{}-part", self.grammar.references[&label_index]);
+                let mut rest = sequence.elements.split_off(1);
+
+                // every part keeps one element and references the next part, until two elements are left
+                let mut part_index = self.add_derived_rule(insert_after, &base, Vec::new());
+                sequence.elements.push(Element::Reference(part_index));
+                while rest.len() > 2 {
+                    let tail = rest.split_off(1);
+                    let next_index = self.add_derived_rule(part_index, &base, Vec::new());
+                    rest.push(Element::Reference(next_index));
+                    *self.sequences_mut(part_index) = vec![Sequence { elements: rest }];
+                    part_index = next_index;
+                    rest = tail;
+                }
+                *self.sequences_mut(part_index) = vec![Sequence { elements: rest }];
+                insert_after = part_index;
+            }
+            *self.sequences_mut(label_index) = sequences;
+        }
+    }
+
+    fn remove_empty(&mut self) {
+        // every sequence is replaced by all the ways of skipping its nullable references
+        let entrypoint = self.grammar.entrypoint();
+        let nullable = self.grammar.nullable_labels();
+        for label_index in self.grammar.labels.clone() {
+            let mut sequences: Vec<Sequence> = Vec::new();
+            for sequence in self.grammar.maps[&label_index].sequences.iter() {
+                let mut variants: Vec<Vec<Element>> = vec![Vec::new()];
+                for element in sequence.elements.iter() {
+                    match element {
+                        Element::Empty => {}
+                        Element::Reference(target) if nullable.contains(target) => {
+                            let skipped = variants.clone();
+                            for variant in variants.iter_mut() {
+                                variant.push(element.clone());
+                            }
+                            variants.extend(skipped);
+                        }
+                        element => {
+                            for variant in variants.iter_mut() {
+                                variant.push(element.clone());
+                            }
+                        }
+                    }
+                }
+                for elements in variants {
+                    let variant = Sequence { elements };
+                    if !variant.elements.is_empty() && !sequences.contains(&variant) {
+                        sequences.push(variant);
+                    }
+                }
+            }
+            if label_index == entrypoint && nullable.contains(&label_index) {
+                sequences.push(Sequence {
+                    elements: vec![Element::Empty],
+                });
+            }
+            *self.sequences_mut(label_index) = sequences;
+        }
+    }
+
+    fn remove_units(&mut self) {
+        // <A> ::= <B> is replaced by everything <B> can be, following chains of such rules
+        let mut replaced = HashMap::new();
+        for &label_index in self.grammar.labels.iter() {
+            let mut visited = vec![label_index];
+            let mut sequences: Vec<Sequence> = Vec::new();
+            let mut i = 0;
+            while i < visited.len() {
+                for sequence in self.grammar.maps[&visited[i]].sequences.iter() {
+                    match sequence.elements.as_slice() {
+                        [Element::Reference(target)] => {
+                            if !visited.contains(target) {
+                                visited.push(*target);
+                            }
+                        }
+                        _ => {
+                            if !sequences.contains(sequence) {
+                                sequences.push(sequence.clone());
+                            }
+                        }
+                    }
+                }
+                i += 1;
+            }
+            replaced.insert(label_index, sequences);
+        }
+        for (label_index, sequences) in replaced {
+            *self.sequences_mut(label_index) = sequences;
+        }
+    }
+
+    fn remove_useless(&mut self) -> Result<(), Error> {
+        // find the rules that can match at least one string
+        let mut generating: HashSet<usize> = HashSet::new();
+        loop {
+            let mut changed = false;
+            for label_index in self.grammar.labels.iter() {
+                if generating.contains(label_index) {
+                    continue;
+                }
+                if self.grammar.maps[label_index]
+                    .sequences
+                    .iter()
+                    .any(|sequence| only_references(sequence, &generating))
+                {
+                    generating.insert(*label_index);
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+        if !generating.contains(&self.grammar.entrypoint()) {
+            return Err(Error::EmptyLanguage);
+        }
+        for alternation in self.grammar.maps.values_mut() {
+            alternation
+                .sequences
+                .retain(|sequence| only_references(sequence, &generating));
+        }
+
+        // find the rules reachable from the entrypoint
+        let mut reachable = vec![self.grammar.entrypoint()];
+        let mut i = 0;
+        while i < reachable.len() {
+            for sequence in self.grammar.maps[&reachable[i]].sequences.iter() {
+                for element in sequence.elements.iter() {
+                    if let Element::Reference(target) = element {
+                        if !reachable.contains(target) {
+                            reachable.push(*target);
+                        }
+                    }
+                }
+            }
+            i += 1;
+        }
+
+        for label_index in self.grammar.labels.clone() {
+            if !generating.contains(&label_index) || !reachable.contains(&label_index) {
+                self.grammar.remove_rule(label_index);
+                self.origins.remove(&label_index);
+            }
+        }
+        Ok(())
+    }
+
+    fn substitute_leading(
+        &mut self,
+        label_index: usize,
+        should_substitute: impl Fn(usize) -> bool,
+    ) {
+        // replace the references starting a sequence by the sequences of the referenced rule,
+        // until no sequence starts with a reference accepted by the predicate
+        loop {
+            let sequences = &self.grammar.maps[&label_index].sequences;
+            let Some(position) = sequences.iter().position(|sequence| {
+                matches!(sequence.elements[0], Element::Reference(corner) if corner != label_index && should_substitute(corner))
+            }) else {
+                return;
+            };
+            let Element::Reference(corner) = sequences[position].elements[0] else {
+                unreachable!("The sequence should start with a reference.");
+            };
+            let rest = Sequence {
+                elements: sequences[position].elements[1..].to_vec(),
+            };
+            let replacements: Vec<Sequence> = self.grammar.maps[&corner]
+                .sequences
+                .iter()
+                .map(|replacement| replacement.concat(&rest))
+                .collect();
+            let sequences = self.sequences_mut(label_index);
+            sequences.remove(position);
+            for (offset, replacement) in replacements.into_iter().enumerate() {
+                if !sequences.contains(&replacement) {
+                    sequences.insert((position + offset).min(sequences.len()), replacement);
+                }
+            }
+        }
+    }
+
+    fn eliminate_direct(&mut self, label_index: usize) -> Option<usize> {
+        // <A> ::= <A> alpha | beta becomes <A> ::= beta | beta <A-tail> and <A-tail> ::= alpha | alpha <A-tail>,
+        // which doesn't need ε unlike the usual rewrite
+        let mut recursive = Vec::new();
+        let mut base = Vec::new();
+        for sequence in self.grammar.maps[&label_index].sequences.iter() {
+            match sequence.elements[0] {
+                Element::Reference(corner) if corner == label_index => recursive.push(Sequence {
+                    elements: sequence.elements[1..].to_vec(),
+                }),
+                _ => base.push(sequence.clone()),
+            }
+        }
+        if recursive.is_empty() {
+            return None;
+        }
+
+        let base_label = format!("{}-tail", self.grammar.references[&label_index]);
+        let tail_index = self.add_derived_rule(label_index, &base_label, Vec::new());
+        let tail = Sequence {
+            elements: vec![Element::Reference(tail_index)],
+        };
+        let mut tail_sequences = recursive.clone();
+        tail_sequences.extend(recursive.iter().map(|alpha| alpha.concat(&tail)));
+        *self.sequences_mut(tail_index) = tail_sequences;
+        let mut sequences = base.clone();
+        sequences.extend(base.iter().map(|beta| beta.concat(&tail)));
+        *self.sequences_mut(label_index) = sequences;
+        Some(tail_index)
+    }
+}
+
+fn only_references(sequence: &Sequence, generating: &HashSet<usize>) -> bool {
+    sequence.elements.iter().all(|element| match element {
+        Element::Reference(target) => generating.contains(target),
+        Element::Empty | Element::Range { .. } | Element::Literal(_) => true,
+    })
+}
+
+fn terminal_label(element: &Element) -> String {
+    match element {
+        Element::Literal(literal) => format!("char-{}", readable_char(literal)),
+        Element::Range { start, end } => format!(
+            "range-{}-{}",
+            readable_char(&start.to_string()),
+            readable_char(&end.to_string())
+        ),
+        Element::Empty | Element::Reference(_) => unreachable!("Only terminals get a rule."),
+    }
+}
+
+fn readable_char(literal: &str) -> String {
+    // keep letters and digits as they are, anything else could clash with the reference syntax
+    literal
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() {
+                c.to_string()
+            } else {
+                format!("U+{:04X}", c as u32)
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,246 @@
+use crate::bnf::alternation::Alternation;
+use crate::bnf::element::Element;
+use crate::bnf::grammar::Grammar;
+use crate::bnf::sequence::Sequence;
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    // the rule was replaced by its body everywhere it was used
+    InlinedRule { label: String },
+    // an ε next to other elements was dropped from an alternative of the rule (counted from 0)
+    RemovedEmpty { label: String, alternative: usize },
+    // an alternative of the rule was the same as a previous one (counted from 0)
+    MergedAlternatives { label: String, alternative: usize },
+    // the rule had the same body as another one, which is now used instead
+    MergedRules { label: String, into: String },
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Change::InlinedRule { label } => write!(f, "inlined <{label}>"),
+            Change::RemovedEmpty { label, alternative } => write!(
+                f,
+                "removed ε from alternative {} of <{label}>",
+                alternative + 1
+            ),
+            Change::MergedAlternatives { label, alternative } => write!(
+                f,
+                "removed duplicate alternative {} of <{label}>",
+                alternative + 1
+            ),
+            Change::MergedRules { label, into } => write!(f, "merged <{label}> into <{into}>"),
+        }
+    }
+}
+
+impl Grammar {
+    pub fn simplify(&self) -> (Grammar, Vec<Change>) {
+        let mut grammar = self.clone();
+        let mut changes = Vec::new();
+
+        // every pass can enable the others, so we run them until nothing changes
+        loop {
+            let count = changes.len();
+            remove_empty_elements(&mut grammar, &mut changes);
+            merge_alternatives(&mut grammar, &mut changes);
+            merge_rules(&mut grammar, &mut changes);
+            inline_rules(&mut grammar, &mut changes);
+            if changes.len() == count {
+                return (grammar, changes);
+            }
+        }
+    }
+}
+
+fn remove_empty_elements(grammar: &mut Grammar, changes: &mut Vec<Change>) {
+    for label_index in grammar.labels.iter() {
+        let alternation = grammar
+            .maps
+            .get_mut(label_index)
+            .expect("Alternation should exist.");
+        for (i, sequence) in alternation.sequences.iter_mut().enumerate() {
+            let simplified = Sequence::from_elements(sequence.elements.clone());
+            if simplified != *sequence {
+                *sequence = simplified;
+                changes.push(Change::RemovedEmpty {
+                    label: grammar.references[label_index].clone(),
+                    alternative: i,
+                });
+            }
+        }
+    }
+}
+
+fn merge_alternatives(grammar: &mut Grammar, changes: &mut Vec<Change>) {
+    for label_index in grammar.labels.iter() {
+        let alternation = grammar
+            .maps
+            .get_mut(label_index)
+            .expect("Alternation should exist.");
+        let mut sequences: Vec<Sequence> = Vec::new();
+        for (i, sequence) in alternation.sequences.drain(..).enumerate() {
+            if sequences.contains(&sequence) {
+                changes.push(Change::MergedAlternatives {
+                    label: grammar.references[label_index].clone(),
+                    alternative: i,
+                });
+            } else {
+                sequences.push(sequence);
+            }
+        }
+        alternation.sequences = sequences;
+    }
+}
+
+fn merge_rules(grammar: &mut Grammar, changes: &mut Vec<Change>) {
+    // rules are split into classes of identical bodies, where references only count through their class,
+    // which also catches recursive rules like <x> ::= "a" <x> and <y> ::= "a" <y>
+    let mut classes: HashMap<usize, usize> = grammar
+        .labels
+        .iter()
+        .map(|&label_index| (label_index, 0))
+        .collect();
+    let mut class_count = 1;
+    loop {
+        let mut signatures: HashMap<(usize, Alternation), usize> = HashMap::new();
+        let mut refined = HashMap::new();
+        for label_index in grammar.labels.iter() {
+            let signature = Alternation {
+                sequences: grammar.maps[label_index]
+                    .sequences
+                    .iter()
+                    .map(|sequence| Sequence {
+                        elements: sequence
+                            .elements
+                            .iter()
+                            .map(|element| match element {
+                                Element::Reference(target) => Element::Reference(classes[target]),
+                                element => element.clone(),
+                            })
+                            .collect(),
+                    })
+                    .collect(),
+            };
+            // the previous class is part of the key so classes only ever get split
+            let next = signatures.len();
+            let class = *signatures
+                .entry((classes[label_index], signature))
+                .or_insert(next);
+            refined.insert(*label_index, class);
+        }
+        classes = refined;
+        if signatures.len() == class_count {
+            break;
+        }
+        class_count = signatures.len();
+    }
+
+    // the earliest rule of each class is kept, so the entrypoint never disappears
+    let mut kept: HashMap<usize, usize> = HashMap::new();
+    for label_index in grammar.labels.clone() {
+        let class = classes[&label_index];
+        match kept.get(&class) {
+            None => {
+                kept.insert(class, label_index);
+            }
+            Some(&into) => {
+                replace_references(grammar, label_index, &[Element::Reference(into)]);
+                changes.push(Change::MergedRules {
+                    label: grammar.references[&label_index].clone(),
+                    into: grammar.references[&into].clone(),
+                });
+                grammar.remove_rule(label_index);
+            }
+        }
+    }
+}
+
+fn inline_rules(grammar: &mut Grammar, changes: &mut Vec<Change>) {
+    let entrypoint = grammar.entrypoint();
+    for label_index in grammar.labels.clone() {
+        if label_index == entrypoint {
+            continue;
+        }
+        let sequences = &grammar.maps[&label_index].sequences;
+
+        // a recursive rule can't be replaced by its body
+        if sequences
+            .iter()
+            .any(|sequence| sequence.elements.contains(&Element::Reference(label_index)))
+        {
+            continue;
+        }
+
+        // a rule made of a single terminal can go everywhere
+        if let [sequence] = sequences.as_slice() {
+            if let [element] = sequence.elements.as_slice() {
+                if !matches!(element, Element::Reference(_)) {
+                    let element = element.clone();
+                    replace_references(grammar, label_index, &[element]);
+                    changes.push(Change::InlinedRule {
+                        label: grammar.references[&label_index].clone(),
+                    });
+                    grammar.remove_rule(label_index);
+                    continue;
+                }
+            }
+        }
+
+        // a rule used once is inlined when it doesn't multiply the surrounding alternative,
+        // that is when it has a single alternative or is the whole alternative using it
+        let mut uses = Vec::new();
+        for (&user_index, alternation) in grammar.maps.iter() {
+            for (i, sequence) in alternation.sequences.iter().enumerate() {
+                for (j, element) in sequence.elements.iter().enumerate() {
+                    if element == &Element::Reference(label_index) {
+                        uses.push((user_index, i, j));
+                    }
+                }
+            }
+        }
+        let [(user_index, i, j)] = uses.as_slice() else {
+            continue;
+        };
+        let (user_index, i, j) = (*user_index, *i, *j);
+        let body = grammar.maps[&label_index].sequences.clone();
+        let user = grammar
+            .maps
+            .get_mut(&user_index)
+            .expect("Alternation should exist.");
+        if let [single] = body.as_slice() {
+            let elements = &mut user.sequences[i].elements;
+            elements.splice(j..j + 1, single.elements.iter().cloned());
+            user.sequences[i] = Sequence::from_elements(elements.clone());
+        } else if user.sequences[i].elements.len() == 1 {
+            user.sequences.splice(i..i + 1, body);
+        } else {
+            continue;
+        }
+        changes.push(Change::InlinedRule {
+            label: grammar.references[&label_index].clone(),
+        });
+        grammar.remove_rule(label_index);
+    }
+}
+
+fn replace_references(grammar: &mut Grammar, label_index: usize, replacement: &[Element]) {
+    for alternation in grammar.maps.values_mut() {
+        for sequence in alternation.sequences.iter_mut() {
+            if !sequence.elements.contains(&Element::Reference(label_index)) {
+                continue;
+            }
+            let mut elements = Vec::new();
+            for element in sequence.elements.iter() {
+                if element == &Element::Reference(label_index) {
+                    elements.extend(replacement.iter().cloned());
+                } else {
+                    elements.push(element.clone());
+                }
+            }
+            *sequence = Sequence::from_elements(elements);
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use std::fmt::Write;
+
+// whether XML 1.0 can hold the character, which rules out most of the control characters, even as
+// character references
+fn is_xml_char(c: char) -> bool {
+    matches!(c, '\t' | '\n' | '\r' | '\u{20}'..='\u{D7FF}' | '\u{E000}'..='\u{FFFD}' | '\u{10000}'..)
+}
+
+// writes a character XML can hold, the ones with a meaning in XML escaped, and the tabs and line
+// breaks as character references so the parsers don't turn them into spaces or normalize them
+fn write_char(output: &mut dyn Write, c: char) -> std::fmt::Result {
+    match c {
+        '<' => write!(output, "&lt;"),
+        '>' => write!(output, "&gt;"),
+        '&' => write!(output, "&amp;"),
+        '"' => write!(output, "&quot;"),
+        '\'' => write!(output, "&apos;"),
+        '\t' | '\n' | '\r' => write!(output, "&#x{:X};", c as u32),
+        c => write!(output, "{c}"),
+    }
+}
+
+// writes the text with the characters that have a meaning in XML escaped, the ones XML can't hold
+// being replaced by U+FFFD, which makes it usable in attributes
+pub fn write_escaped(output: &mut dyn Write, text: &str) -> std::fmt::Result {
+    for c in text.chars() {
+        write_char(output, if is_xml_char(c) { c } else { '\u{FFFD}' })?;
+    }
+    Ok(())
+}
+
+// writes the text as the content of an element, like write_escaped, but with the characters XML
+// can't hold written as <char code="N"/> elements, so none of them is lost
+pub fn write_content(output: &mut dyn Write, text: &str) -> std::fmt::Result {
+    for c in text.chars() {
+        if is_xml_char(c) {
+            write_char(output, c)?;
+        } else {
+            write!(output, "<char code=\"{}\"/>", c as u32)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,138 @@
+mod common;
+
+use backus_naur_parser::analysis::equivalence::{Error as EquivalenceError, Options};
+use backus_naur_parser::analysis::lint::{lint, Lint};
+use common::{example, grammar, lenient};
+
+#[test]
+fn compare_finds_the_shortest_difference() {
+    let first = grammar("<s> ::= \"a\" <s> | \"b\"\n");
+    let second = grammar("<s> ::= \"a\" \"a\" <s> | \"b\"\n");
+    let comparison = first
+        .compare(&second, &Options::default())
+        .expect("Neither grammar should overflow the stack.");
+    let difference = comparison.difference.expect("The grammars should differ.");
+    assert_eq!(difference.input, "ab");
+    assert!(difference.accepted_by_first);
+
+    let comparison = second
+        .compare(&first, &Options::default())
+        .expect("Neither grammar should overflow the stack.");
+    let difference = comparison.difference.expect("The grammars should differ.");
+    assert_eq!(difference.input, "ab");
+    assert!(!difference.accepted_by_first);
+}
+
+#[test]
+fn compare_equivalent_grammars() {
+    let first = grammar("<s> ::= \"a\" <s> | ε\n");
+    let second = grammar("<s> ::= <s2> | ε\n<s2> ::= \"a\" | \"a\" <s2>\n");
+    let options = Options {
+        max_length: 6,
+        ..Options::default()
+    };
+    let comparison = first
+        .compare(&second, &options)
+        .expect("Neither grammar should overflow the stack.");
+    assert!(comparison.difference.is_none());
+    assert_eq!(comparison.exhaustive_length, Some(6));
+    // the empty input, then a single character for each length
+    assert_eq!(comparison.checked, 7);
+}
+
+#[test]
+fn compare_left_recursive_grammar() {
+    let first = grammar("<s> ::= <s> \"a\" | \"a\"\n");
+    let second = grammar("<s> ::= \"a\" <s> | \"a\"\n");
+    let result = first.compare(&second, &Options::default());
+    assert!(matches!(
+        result,
+        Err(EquivalenceError::StackOverflow { .. })
+    ));
+}
+
+#[test]
+fn metrics_of_the_equation_example() {
+    let mut report = String::new();
+    example("equation.bnf")
+        .metrics()
+        .write_text(&mut report)
+        .expect("Writing to a String should not fail.");
+    assert_eq!(
+        report,
+        "rules: 6
+alternatives per rule: min 1, max 3, average 2.17
+  <expr>: 3
+  <term>: 3
+  <factor>: 2
+  <number>: 2
+  <digit>: 1
+  <opt-space>: 2
+max sequence length: 5
+recursion depth: 3
+terminals: 8
+characters covered by ranges: 10
+nullable rules: 1
+strongly connected components: 4
+  recursive: <opt-space>
+  recursive: <number>
+  recursive: <expr> <term> <factor>
+"
+    );
+}
+
+#[test]
+fn metrics_as_json() {
+    let mut json = String::new();
+    grammar("<s> ::= \"a\"..=\"z\" <s> | \"é\" | ε\n")
+        .metrics()
+        .write_json(&mut json)
+        .expect("Writing to a String should not fail.");
+    assert_eq!(
+        json,
+        "{\"rules\":1,\"alternatives\":{\"s\":3},\"min_alternatives\":3,\"max_alternatives\":3,\"average_alternatives\":3.00,\"max_sequence_length\":2,\"recursion_depth\":1,\"terminals\":2,\"range_characters\":26,\"nullable_rules\":1,\"components\":1,\"recursive_components\":[[\"s\"]]}\n"
+    );
+}
+
+#[test]
+fn metrics_of_a_grammar_with_an_undefined_rule() {
+    let mut grammar = grammar("<a> ::= <b> \"x\" | <c>\n<c> ::= <c> \"y\" | <b>\n<b> ::= \"z\"\n");
+    let b = grammar.label_index("b").expect("<b> should be defined.");
+    // what the lenient parser gives for a reference to a rule that isn't defined
+    grammar.labels.retain(|label_index| *label_index != b);
+    grammar.maps.remove(&b);
+    let metrics = grammar.metrics();
+    assert_eq!(metrics.rules, 2);
+    assert_eq!(metrics.recursion_depth, 2);
+    assert_eq!(metrics.recursive_components, vec![vec!["c".to_string()]]);
+}
+
+#[test]
+fn lints_of_a_grammar_with_an_undefined_rule() {
+    let grammar = lenient("<a> ::= <b> \"x\" | <c>\n<c> ::= \"y\"\n");
+    let lints: Vec<Lint> = lint(&grammar)
+        .iter()
+        .map(|diagnostic| diagnostic.lint)
+        .collect();
+    assert_eq!(lints, vec![Lint::UndefinedRule]);
+}
+
+#[test]
+fn lints_of_alternatives_deriving_no_string() {
+    let grammar = lenient("<a> ::= \"y\" | <b>\n<b> ::= \"z\" <b>\n");
+    let diagnostics: Vec<(Lint, String, Option<usize>)> = lint(&grammar)
+        .into_iter()
+        .map(|diagnostic| (diagnostic.lint, diagnostic.rule, diagnostic.alternative))
+        .collect();
+    assert_eq!(
+        diagnostics,
+        vec![
+            (Lint::UnproductiveAlternative, "a".to_string(), Some(1)),
+            (Lint::UnproductiveAlternative, "b".to_string(), Some(0)),
+        ]
+    );
+    assert_eq!(
+        Lint::from_name("unproductive-alternative"),
+        Some(Lint::UnproductiveAlternative)
+    );
+}
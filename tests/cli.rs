@@ -0,0 +1,545 @@
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+struct Run {
+    code: i32,
+    stdout: String,
+    stderr: String,
+}
+
+// runs the bnf binary in the directory, with the text as its standard input
+fn bnf(directory: &Path, arguments: &[&str], stdin: &str) -> Run {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_bnf"))
+        .args(arguments)
+        .current_dir(directory)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("The binary should start.");
+    child
+        .stdin
+        .take()
+        .expect("The standard input should be piped.")
+        .write_all(stdin.as_bytes())
+        .expect("The standard input should be writable.");
+    let output = child.wait_with_output().expect("The binary should run.");
+    Run {
+        code: output.status.code().expect("The binary should exit."),
+        stdout: String::from_utf8(output.stdout).expect("The output should be UTF-8."),
+        stderr: String::from_utf8(output.stderr).expect("The errors should be UTF-8."),
+    }
+}
+
+// a directory of its own for every test, holding the files
+fn directory(test: &str, files: &[(&str, &str)]) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("bnf-{test}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    for (name, content) in files {
+        let path = directory.join(name);
+        std::fs::create_dir_all(path.parent().expect("The file should be in a directory."))
+            .expect("The directory should be created.");
+        std::fs::write(path, content).expect("The file should be written.");
+    }
+    directory
+}
+
+const GREETING: &str =
+    "<greeting> ::= \"hello \" <name>\n<name> ::= \"a\"..=\"z\" <name> | \"a\"..=\"z\"\n";
+
+#[test]
+fn parse_exit_codes() {
+    let directory = directory(
+        "parse",
+        &[
+            ("greeting.bnf", GREETING),
+            ("invalid.bnf", "<greeting> ::= <name>\n"),
+            ("good.txt", "hello bob"),
+            ("bad.txt", "hello Bob"),
+        ],
+    );
+
+    let run = bnf(&directory, &["parse", "-g", "greeting.bnf", "good.txt"], "");
+    assert_eq!((run.code, run.stdout.as_str()), (0, "accepted\n"));
+    let run = bnf(&directory, &["parse", "-g", "greeting.bnf", "bad.txt"], "");
+    assert_eq!((run.code, run.stdout.as_str()), (1, "rejected: no match\n"));
+    let run = bnf(
+        &directory,
+        &["parse", "--grammar=greeting.bnf"],
+        "hello alice",
+    );
+    assert_eq!((run.code, run.stdout.as_str()), (0, "accepted\n"));
+    let run = bnf(
+        &directory,
+        &["parse", "-q", "-g", "greeting.bnf", "-"],
+        "hi",
+    );
+    assert_eq!((run.code, run.stdout.as_str()), (1, ""));
+
+    let run = bnf(
+        &directory,
+        &["parse", "-g", "greeting.bnf", "--format", "json", "bad.txt"],
+        "",
+    );
+    assert_eq!(run.code, 1);
+    assert_eq!(
+        run.stdout,
+        "{\"status\":\"rejected\",\"reason\":\"no_match\",\"grammar\":\"greeting.bnf\",\"input\":\"bad.txt\"}\n"
+    );
+
+    let run = bnf(&directory, &["parse", "-g", "invalid.bnf", "good.txt"], "");
+    assert_eq!(run.code, 2);
+    assert!(run.stderr.contains("<name>"), "{}", run.stderr);
+    let run = bnf(
+        &directory,
+        &["parse", "-g", "greeting.bnf", "missing.txt"],
+        "",
+    );
+    assert_eq!(run.code, 2);
+    assert!(run
+        .stderr
+        .starts_with("error: missing.txt: couldn't read the input"));
+    let run = bnf(&directory, &["parse", "good.txt"], "");
+    assert_eq!(run.code, 2);
+    assert!(run.stderr.starts_with("error: missing `--grammar <file>`"));
+    let run = bnf(&directory, &["frobnicate"], "");
+    assert_eq!(run.code, 2);
+    std::fs::remove_dir_all(&directory).expect("The directory should be removed.");
+}
+
+#[test]
+fn check_severities() {
+    let directory = directory(
+        "check",
+        &[
+            ("greeting.bnf", GREETING),
+            (
+                "lints.bnf",
+                "<s> ::= <s> \"a\" | <t>\n<t> ::= \"b\" | \"b\"\n<unused> ::= \"c\"\n",
+            ),
+            (
+                "relaxed.toml",
+                "# fine for now\nunused-rule = allow\nleft-recursion = warn\n",
+            ),
+            ("broken.toml", "unused-rule allow\n"),
+        ],
+    );
+
+    let run = bnf(&directory, &["check", "greeting.bnf"], "");
+    assert_eq!((run.code, run.stdout.as_str()), (0, ""));
+    let run = bnf(&directory, &["check", "lints.bnf"], "");
+    assert_eq!(run.code, 1);
+    assert_eq!(
+        run.stdout,
+        "error[left-recursion]: lints.bnf:1:1: <s> is left recursive, the parser overflows its stack on it
+warning[duplicate-alternative]: lints.bnf:2:1: alternative 2 of <t> repeats alternative 1
+warning[unused-rule]: lints.bnf:3:1: <unused> can't be reached from the entrypoint
+lints.bnf: 1 error, 2 warnings
+"
+    );
+
+    // the config relaxes the lints, and the flags win over it
+    let run = bnf(
+        &directory,
+        &["check", "--config", "relaxed.toml", "lints.bnf"],
+        "",
+    );
+    assert_eq!(run.code, 0);
+    assert!(run.stdout.ends_with("lints.bnf: 0 errors, 2 warnings\n"));
+    assert!(!run.stdout.contains("unused-rule"));
+    let run = bnf(
+        &directory,
+        &[
+            "check",
+            "-D",
+            "duplicate-alternative",
+            "--config=relaxed.toml",
+            "lints.bnf",
+        ],
+        "",
+    );
+    assert_eq!(run.code, 1);
+    assert!(run.stdout.contains("error[duplicate-alternative]"));
+    let run = bnf(
+        &directory,
+        &[
+            "check",
+            "-A",
+            "left-recursion",
+            "-A",
+            "duplicate-alternative",
+            "-A",
+            "unused-rule",
+            "--format",
+            "json",
+            "-v",
+            "lints.bnf",
+        ],
+        "",
+    );
+    assert_eq!(run.code, 0);
+    assert!(run.stdout.starts_with(
+        "{\"grammar\":\"lints.bnf\",\"errors\":0,\"warnings\":0,\"diagnostics\":[{\"lint\":\"left-recursion\",\"severity\":\"allow\",\"rule\":\"s\",\"line\":1,\"column\":1,"
+    ));
+
+    let run = bnf(&directory, &["check", "-q", "lints.bnf"], "");
+    assert_eq!((run.code, run.stdout.as_str()), (1, ""));
+    let run = bnf(
+        &directory,
+        &["check", "--config", "broken.toml", "lints.bnf"],
+        "",
+    );
+    assert_eq!(run.code, 2);
+    assert_eq!(
+        run.stderr,
+        "error: broken.toml:1: expected `lint = severity`\n"
+    );
+    let run = bnf(
+        &directory,
+        &["check", "-D", "no-such-lint", "lints.bnf"],
+        "",
+    );
+    assert_eq!(run.code, 2);
+    assert!(run.stderr.starts_with("error: unknown lint `no-such-lint`"));
+    std::fs::remove_dir_all(&directory).expect("The directory should be removed.");
+}
+
+#[test]
+fn fmt_check_and_rewrite() {
+    let messy = "<list>::=<item>   \",\" <list>|<item>\n\n<item> ::= \"x\"|\"y\" |   ε\n";
+    let directory = directory(
+        "fmt",
+        &[("messy.bnf", messy), ("invalid.bnf", "<list> ::= <item>\n")],
+    );
+
+    let formatted = bnf(&directory, &["fmt"], messy);
+    assert_eq!(formatted.code, 0);
+    assert_eq!(
+        formatted.stdout,
+        "<list> ::= <item> \",\" <list> | <item>\n<item> ::= \"x\" | \"y\" | ε\n"
+    );
+    // formatting twice changes nothing
+    let run = bnf(&directory, &["fmt"], &formatted.stdout);
+    assert_eq!(
+        (run.code, run.stdout.as_str()),
+        (0, formatted.stdout.as_str())
+    );
+
+    let run = bnf(&directory, &["fmt", "--check", "messy.bnf"], "");
+    assert_eq!(
+        (run.code, run.stdout.as_str()),
+        (1, "messy.bnf: not formatted\n")
+    );
+    let run = bnf(&directory, &["fmt", "-v", "messy.bnf"], "");
+    assert_eq!(
+        (run.code, run.stdout.as_str()),
+        (0, "messy.bnf: formatted\n")
+    );
+    let rewritten = std::fs::read_to_string(directory.join("messy.bnf"))
+        .expect("The grammar should be readable.");
+    assert_eq!(rewritten, formatted.stdout);
+    let run = bnf(
+        &directory,
+        &["fmt", "--check", "--format", "json", "messy.bnf"],
+        "",
+    );
+    assert_eq!(
+        (run.code, run.stdout.as_str()),
+        (0, "{\"file\":\"messy.bnf\",\"changed\":false}\n")
+    );
+
+    let run = bnf(
+        &directory,
+        &["fmt", "--check", "messy.bnf", "invalid.bnf"],
+        "",
+    );
+    assert_eq!(run.code, 2);
+    assert!(
+        run.stderr.starts_with("error: invalid.bnf: 1:"),
+        "{}",
+        run.stderr
+    );
+    std::fs::remove_dir_all(&directory).expect("The directory should be removed.");
+}
+
+#[test]
+fn repl_piped_session() {
+    let directory = directory("repl", &[("greeting.bnf", GREETING)]);
+    let session = "hello bob\nhello\n:tree off\n<name> ::= \"B\" <name> | \"b\"\nhello Bb\n:start name\nBBb\n:parse :rules\n:reload\nhello Bb\n:frobnicate\n:quit\nhello bob\n";
+    let run = bnf(&directory, &["repl", "greeting.bnf"], session);
+    assert_eq!(run.code, 0);
+    assert_eq!(
+        run.stdout,
+        "accepted
+<greeting> alternative 1, 0..9 \"hello bob\"
+  \"hello \"
+  <name> alternative 1, 6..9 \"bob\"
+    \"b\"
+    <name> alternative 1, 7..9 \"ob\"
+      \"o\"
+      <name> alternative 2, 8..9 \"b\"
+        \"b\"
+rejected
+redefined <name>
+accepted
+parsing from <name>
+accepted
+rejected
+2 rules loaded
+rejected
+unknown command :frobnicate, :help lists the commands
+"
+    );
+}
+
+#[test]
+fn corpus_runs() {
+    let directory = directory(
+        "corpus",
+        &[
+            ("greeting.bnf", GREETING),
+            ("corpus/b.txt", "hello Bob"),
+            ("corpus/a.txt", "hello alice"),
+            ("extra.txt", "hello carol"),
+        ],
+    );
+
+    let run = bnf(
+        &directory,
+        &["parse", "-g", "greeting.bnf", "corpus", "extra.txt"],
+        "",
+    );
+    assert_eq!(run.code, 1);
+    assert_eq!(
+        run.stdout,
+        "[run 1] 2 passed, 1 failed\n  fail corpus/b.txt: rejected\n"
+    );
+    let run = bnf(
+        &directory,
+        &["parse", "-g", "greeting.bnf", "corpus/a.txt", "extra.txt"],
+        "",
+    );
+    assert_eq!(
+        (run.code, run.stdout.as_str()),
+        (0, "[run 1] 2 passed, 0 failed\n")
+    );
+    let run = bnf(
+        &directory,
+        &["parse", "-g", "greeting.bnf", "--tree", "json", "corpus"],
+        "",
+    );
+    assert_eq!(run.code, 2);
+    std::fs::remove_dir_all(&directory).expect("The directory should be removed.");
+}
+
+#[test]
+fn watch_reports_changes() {
+    let directory = directory(
+        "watch",
+        &[
+            ("greeting.bnf", GREETING),
+            ("corpus/a.txt", "hello alice"),
+            ("corpus/b.txt", "hello Bob"),
+        ],
+    );
+    let mut child = Command::new(env!("CARGO_BIN_EXE_bnf"))
+        .args(["parse", "-g", "greeting.bnf", "--interval", "20", "corpus"])
+        .current_dir(&directory)
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("The binary should start.");
+    let mut lines = BufReader::new(child.stdout.take().expect("The output should be piped."))
+        .lines()
+        .map(|line| line.expect("The output should be readable."));
+
+    assert_eq!(lines.next().as_deref(), Some("[run 1] 1 passed, 1 failed"));
+    assert_eq!(
+        lines.next().as_deref(),
+        Some("  fail corpus/b.txt: rejected")
+    );
+    std::fs::write(
+        directory.join("greeting.bnf"),
+        // a change of size, so it is seen even when the modification time doesn't move
+        GREETING.replace("<name> ::= ", "<name> ::= \"B\" <name> | "),
+    )
+    .expect("The grammar should be written.");
+    assert_eq!(lines.next().as_deref(), Some("[run 2] 2 passed, 0 failed"));
+    assert_eq!(lines.next().as_deref(), Some("  now passing corpus/b.txt"));
+    std::fs::write(directory.join("corpus/c.txt"), "hi").expect("The input should be written.");
+    assert_eq!(lines.next().as_deref(), Some("[run 3] 2 passed, 1 failed"));
+    assert_eq!(
+        lines.next().as_deref(),
+        Some("  new corpus/c.txt: rejected")
+    );
+
+    child.kill().expect("The watcher should stop.");
+    child.wait().expect("The watcher should exit.");
+    std::fs::remove_dir_all(&directory).expect("The directory should be removed.");
+}
+
+#[test]
+fn parse_traced() {
+    let directory = directory(
+        "trace",
+        &[("ab.bnf", "<s> ::= <a> \"b\" | <a> \"c\"\n<a> ::= \"a\"\n")],
+    );
+
+    let run = bnf(&directory, &["parse", "-g", "ab.bnf", "--trace"], "ab");
+    assert_eq!(run.code, 0);
+    assert_eq!(
+        run.stdout,
+        "enter <s> at 0
+try <s> alternative 1 at 0
+  enter <a> at 0
+  try <a> alternative 1 at 0
+  match \"a\" at 0..1
+  exit <a> alternative 1 at 0..1
+match \"b\" at 1..2
+exit <s> alternative 1 at 0..2
+accepted
+"
+    );
+    // every step is a JSON object on its own line, the result too
+    let run = bnf(
+        &directory,
+        &["parse", "-g", "ab.bnf", "-t", "--format", "json"],
+        "ax",
+    );
+    assert_eq!(run.code, 1);
+    let lines: Vec<&str> = run.stdout.lines().collect();
+    assert_eq!(
+        lines[0],
+        "{\"event\":\"enter\",\"depth\":1,\"rule\":\"s\",\"offset\":0}"
+    );
+    assert!(lines
+        .iter()
+        .all(|line| line.starts_with('{') && line.ends_with('}')));
+    assert!(lines[lines.len() - 1].starts_with("{\"status\":\"rejected\",\"reason\":\"no_match\""));
+    // quiet prints neither the steps nor the result
+    let run = bnf(&directory, &["parse", "-g", "ab.bnf", "-t", "-q"], "ab");
+    assert_eq!((run.code, run.stdout.as_str()), (0, ""));
+    let run = bnf(&directory, &["parse", "-g", "ab.bnf", "-t", "-p"], "ab");
+    assert_eq!(run.code, 2);
+    std::fs::remove_dir_all(&directory).expect("The directory should be removed.");
+}
+
+#[test]
+fn debug_piped_session() {
+    let directory = directory(
+        "debug",
+        &[
+            ("ab.bnf", "<s> ::= <a> \"b\" | <a> \"c\"\n<a> ::= \"a\"\n"),
+            ("input.txt", "ac"),
+        ],
+    );
+
+    let session = "break 1\nbreakpoints\nc\nbt\ninput\nd <a>\nc\n";
+    let run = bnf(
+        &directory,
+        &["debug", "-g", "ab.bnf", "-b", "a", "input.txt"],
+        session,
+    );
+    assert_eq!(run.code, 0);
+    // the offset breakpoint pauses again once the parser backtracked to <s> and matched \"a\" anew
+    assert_eq!(
+        run.stdout,
+        "breakpoint on <a>
+enter <a> at 0 (depth 2)
+breakpoint on offset 1
+<a>
+offset 1
+breakpoint on offset 1
+match \"a\" at 0..1 (depth 2)
+#0 <s> alternative 1 at 0
+#1   <a> alternative 1 at 0, from <s> alternative 1 element 1
+\"a\" | \"c\"
+removed the breakpoint on <a>
+breakpoint on offset 1
+match \"a\" at 0..1 (depth 2)
+accepted
+"
+    );
+    let run = bnf(
+        &directory,
+        &["debug", "-g", "ab.bnf", "input.txt"],
+        "s\nq\n",
+    );
+    assert_eq!(run.code, 1);
+    assert!(run.stdout.ends_with("stopped\n"));
+    let run = bnf(
+        &directory,
+        &["debug", "-g", "ab.bnf", "-b", "z", "input.txt"],
+        "",
+    );
+    assert_eq!(run.code, 2);
+    std::fs::remove_dir_all(&directory).expect("The directory should be removed.");
+}
+
+#[test]
+fn parse_profiled() {
+    let directory = directory("profile", &[("greeting.bnf", GREETING)]);
+
+    let run = bnf(
+        &directory,
+        &["parse", "-g", "greeting.bnf", "--profile"],
+        "hello bob",
+    );
+    assert_eq!(run.code, 0);
+    let lines: Vec<&str> = run.stdout.lines().collect();
+    assert!(lines[0].starts_with("rule "));
+    // the table, two rules of one and two alternatives, comes before the result
+    assert_eq!(lines.len(), 8);
+    assert!(lines[6].starts_with("total "));
+    assert_eq!(lines[7], "accepted");
+    let run = bnf(
+        &directory,
+        &["parse", "-g", "greeting.bnf", "-p", "--format", "json"],
+        "hello",
+    );
+    assert_eq!(run.code, 1);
+    let lines: Vec<&str> = run.stdout.lines().collect();
+    assert!(lines[0].starts_with("{\"rules\":["));
+    assert!(lines[1].starts_with("{\"status\":\"rejected\""));
+    std::fs::remove_dir_all(&directory).expect("The directory should be removed.");
+}
+
+#[test]
+fn parse_tree_formats() {
+    let directory = directory("tree", &[("greeting.bnf", GREETING)]);
+
+    let run = bnf(
+        &directory,
+        &["parse", "-g", "greeting.bnf", "--tree", "sexp"],
+        "hello al",
+    );
+    assert_eq!(run.code, 0);
+    assert_eq!(
+        run.stdout,
+        "(rule \"greeting\" (alternative 1) (span 0 8) (terminal (span 0 6) \"hello \") (rule \"name\" (alternative 1) (span 6 8) (terminal (span 6 7) \"a\") (rule \"name\" (alternative 2) (span 7 8) (terminal (span 7 8) \"l\"))))\n"
+    );
+    let run = bnf(
+        &directory,
+        &["parse", "-g", "greeting.bnf", "--tree=xml"],
+        "hello al",
+    );
+    assert_eq!(run.code, 0);
+    assert!(run
+        .stdout
+        .starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<rule name=\"greeting\""));
+    // a rejected input has no tree, the result is printed instead
+    let run = bnf(
+        &directory,
+        &["parse", "-g", "greeting.bnf", "--tree", "json"],
+        "hello",
+    );
+    assert_eq!((run.code, run.stdout.as_str()), (1, "rejected: no match\n"));
+    let run = bnf(
+        &directory,
+        &["parse", "-g", "greeting.bnf", "--tree", "yaml"],
+        "hello al",
+    );
+    assert_eq!(run.code, 2);
+    assert!(run.stderr.starts_with("error: unknown tree format `yaml`"));
+    std::fs::remove_dir_all(&directory).expect("The directory should be removed.");
+}
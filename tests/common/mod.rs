@@ -0,0 +1,39 @@
+// helpers shared by the test files, each of them using only some
+#![allow(dead_code)]
+
+use backus_naur_parser::analysis::equivalence::Options;
+use backus_naur_parser::bnf::grammar::{parse_grammar, parse_grammar_lenient, Grammar};
+
+pub fn grammar(text: &str) -> Grammar {
+    let chars: Vec<char> = text.chars().collect();
+    parse_grammar(&chars, 0).expect("The grammar should be valid.")
+}
+
+// a grammar that may use undefined rules, for the lints
+pub fn lenient(text: &str) -> Grammar {
+    let chars: Vec<char> = text.chars().collect();
+    parse_grammar_lenient(&chars, 0).expect("The grammar should be valid.")
+}
+
+pub fn example(name: &str) -> Grammar {
+    let path = format!("{}/examples/{name}", env!("CARGO_MANIFEST_DIR"));
+    grammar(&std::fs::read_to_string(path).expect("The example should be readable."))
+}
+
+// the inputs up to 5 characters are all tried when the grammars use a few characters, and sampled
+// otherwise
+pub fn assert_equivalent(first: &Grammar, second: &Grammar) {
+    let options = Options {
+        max_length: 5,
+        max_inputs_per_length: 2_000,
+        seed: 0,
+    };
+    let comparison = first
+        .compare(second, &options)
+        .expect("Neither grammar should overflow the stack.");
+    assert!(
+        comparison.difference.is_none(),
+        "{:?} is only accepted by one of the grammars",
+        comparison.difference
+    );
+}
@@ -0,0 +1,288 @@
+mod common;
+
+use backus_naur_parser::bnf::grammar::Grammar;
+use backus_naur_parser::export::abnf::write_abnf;
+use backus_naur_parser::export::dot::write_dot;
+use backus_naur_parser::export::iso_ebnf::write_iso_ebnf;
+use backus_naur_parser::export::pest::{pest_warnings, write_pest};
+use backus_naur_parser::export::railroad::{file_names, rule_svg, write_railroad};
+use backus_naur_parser::export::rust::{build_parser, write_rust, Error as RustError};
+use backus_naur_parser::export::tree_sitter::{tree_sitter_warnings, write_tree_sitter};
+use backus_naur_parser::export::w3c_ebnf::write_w3c_ebnf;
+use common::{example, grammar};
+use std::fmt::Write;
+use std::process::Command;
+
+fn tree_sitter(grammar: &Grammar) -> String {
+    let mut output = String::new();
+    write_tree_sitter(grammar, "test", &mut output).expect("Writing to a String should not fail.");
+    output
+}
+
+// compiles the parser generated from the grammar into a program printing, for each input, the error
+// the parser returns if any
+fn rust_parser_errors(grammar: &Grammar, inputs: &[&str], name: &str) -> Vec<String> {
+    let mut code = String::new();
+    write_rust(grammar, &mut code).expect("Writing to a String should not fail.");
+    writeln!(code, "\nfn main() {{").expect("Writing to a String should not fail.");
+    for input in inputs {
+        writeln!(code, "    println!(\"{{:?}}\", parse({input:?}).err());")
+            .expect("Writing to a String should not fail.");
+    }
+    writeln!(code, "}}").expect("Writing to a String should not fail.");
+
+    let directory = std::env::temp_dir().join(format!("{name}-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).expect("The directory should be created.");
+    let source = directory.join("parser.rs");
+    std::fs::write(&source, code).expect("The parser should be written.");
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let status = Command::new(rustc)
+        .arg("--edition=2021")
+        .arg("-o")
+        .arg(directory.join("parser"))
+        .arg(&source)
+        .status()
+        .expect("rustc should run.");
+    assert!(status.success(), "the generated parser doesn't compile");
+    let output = Command::new(directory.join("parser"))
+        .output()
+        .expect("The parser should run.");
+    std::fs::remove_dir_all(&directory).expect("The directory should be removed.");
+    std::str::from_utf8(&output.stdout)
+        .expect("The output should be UTF-8.")
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
+
+#[test]
+fn railroad_file_names() {
+    let grammar = grammar("<a+b> ::= <a_b> | \"x\"\n<a_b> ::= \"<\" | ε\n");
+    let names = file_names(&grammar);
+    assert_eq!(names[&grammar.labels[0]], "a_b.svg");
+    assert_eq!(names[&grammar.labels[1]], "a_b-2.svg");
+}
+
+#[test]
+fn railroad_diagrams_link_their_references() {
+    let grammar = grammar("<a+b> ::= <a_b> | \"x\"\n<a_b> ::= \"<\" | ε\n");
+    let svg = rule_svg(&grammar, grammar.labels[0]);
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    assert!(svg.contains("&lt;a+b&gt;</text>"));
+    assert!(svg.contains("<a href=\"a_b-2.svg\" xlink:href=\"a_b-2.svg\">"));
+    // the terminals are written like in the grammar, escaped for XML
+    let svg = rule_svg(&grammar, grammar.labels[1]);
+    assert!(svg.contains("&quot;&lt;&quot;</text>"));
+
+    let directory = std::env::temp_dir().join(format!("railroad-{}", std::process::id()));
+    write_railroad(&grammar, &directory).expect("The diagrams should be written.");
+    for file in ["a_b.svg", "a_b-2.svg", "index.html"] {
+        assert!(directory.join(file).is_file(), "{file} is missing");
+    }
+    let index = std::fs::read_to_string(directory.join("index.html"))
+        .expect("The index should be readable.");
+    assert!(index.contains("<object type=\"image/svg+xml\" data=\"a_b-2.svg\"></object>"));
+    std::fs::remove_dir_all(&directory).expect("The directory should be removed.");
+}
+
+#[test]
+fn dot_graph_of_the_rules() {
+    let grammar = grammar(
+        "<s> ::= <e> | <s> \"x\" <e>\n<e> ::= \"(\" <s> \")\" | <t> | <t> <t>\n<t> ::= \"a\"\n",
+    );
+    let mut dot = String::new();
+    write_dot(&grammar, &mut dot).expect("Writing to a String should not fail.");
+    assert_eq!(
+        dot,
+        "digraph grammar {
+  node [shape=box, fontname=\"monospace\"];
+  edge [fontname=\"monospace\"];
+  \"s\" [peripheries=2, style=bold, color=red];
+  \"e\" [color=red];
+  \"t\";
+  \"s\" -> \"e\" [label=\"1,2\", color=red, fontcolor=red];
+  \"s\" -> \"s\" [label=\"2\", color=red, fontcolor=red];
+  \"e\" -> \"s\" [label=\"1\", color=red, fontcolor=red];
+  \"e\" -> \"t\" [label=\"2,3\"];
+}
+"
+    );
+}
+
+#[test]
+fn standard_notations() {
+    let grammar = grammar("<list> ::= <item> \",\" <list> | <item> | ε\n<item> ::= \"0\"..=\"9\" | #\"say \"hi\"\"# | \"ab\" | \"é\"..=\"ü\"\n");
+    let mut abnf = String::new();
+    write_abnf(&grammar, &mut abnf).expect("Writing to a String should not fail.");
+    let mut iso = String::new();
+    write_iso_ebnf(&grammar, &mut iso).expect("Writing to a String should not fail.");
+    let mut w3c = String::new();
+    write_w3c_ebnf(&grammar, &mut w3c).expect("Writing to a String should not fail.");
+    assert_eq!(
+        abnf,
+        "list = [item \",\" list / item]\r\nitem = %x30-39 / %x73.61.79.20.22.68.69.22 / %x61.62 / %xE9-FC\r\n"
+    );
+    assert_eq!(
+        iso,
+        "list = [item, \",\", list | item] ;\nitem = (\"0\" | \"1\" | \"2\" | \"3\" | \"4\" | \"5\" | \"6\" | \"7\" | \"8\" | \"9\") | 'say \"hi\"' | \"ab\" | (\"é\" | \"ê\" | \"ë\" | \"ì\" | \"í\" | \"î\" | \"ï\" | \"ð\" | \"ñ\" | \"ò\" | \"ó\" | \"ô\" | \"õ\" | \"ö\" | \"÷\" | \"ø\" | \"ù\" | \"ú\" | \"û\" | \"ü\") ;\n"
+    );
+    assert_eq!(
+        w3c,
+        "list ::= (item \",\" list | item)?\nitem ::= [0-9] | 'say \"hi\"' | \"ab\" | [#xE9-#xFC]\n"
+    );
+}
+
+#[test]
+fn pest_rules() {
+    let grammar = grammar("<main> ::= <type> <opt-space> | \"fn\" \"(\" <main> \")\"\n<type> ::= \"f\" | \"fn\"\n<opt-space> ::= \" \" <opt-space> | ε\n");
+    let mut pest = String::new();
+    write_pest(&grammar, &mut pest).expect("Writing to a String should not fail.");
+    assert_eq!(
+        pest,
+        "// warning: alternatives 1 and 2 of <main> can start alike, pest never tries the second one once the first matched
+// warning: alternatives 1 and 2 of <type> can start alike, pest never tries the second one once the first matched
+main2 = { SOI ~ main ~ EOI }
+main = { type_rule ~ opt_space | \"fn\" ~ \"(\" ~ main ~ \")\" }
+type_rule = { \"f\" | \"fn\" }
+opt_space = { (\" \" ~ opt_space)? }
+"
+    );
+}
+
+#[test]
+fn pest_left_recursion() {
+    let warnings: Vec<String> =
+        pest_warnings(&grammar("<e> ::= <e> \"+\" <n> | <n>\n<n> ::= \"1\"\n"))
+            .iter()
+            .map(|warning| warning.to_string())
+            .collect();
+    assert_eq!(
+        warnings,
+        vec![
+            "<e> is left recursive, pest won't accept it",
+            "alternatives 1 and 2 of <e> can start alike, pest never tries the second one once the first matched",
+        ]
+    );
+}
+
+#[test]
+fn tree_sitter_conflicts_and_warnings() {
+    let output = tree_sitter(&grammar(
+        "<e> ::= <e> \"+\" <n> | <a> | <b>\n<a> ::= \"x\" <opt>\n<b> ::= \"x\" \"y\"\n<opt> ::= \"z\" | ε\n<n> ::= \"1\"\n",
+    ));
+    assert_eq!(
+        output,
+        "// warning: <e> is left recursive, it needs a precedence if tree-sitter reports it as ambiguous
+// warning: <a> and <b> can start alike, they are declared as a conflict
+module.exports = grammar({
+  name: 'test',
+  extras: $ => [],
+  conflicts: $ => [
+    [$.a, $.b],
+  ],
+  rules: {
+    e: $ => choice(seq($.e, '+', $.n), $.a, $.b),
+    a: $ => seq('x', optional($.opt)),
+    b: $ => seq('x', 'y'),
+    opt: $ => 'z',
+    n: $ => '1',
+  },
+});
+"
+    );
+
+    let warnings: Vec<String> = tree_sitter_warnings(&grammar(
+        "<s> ::= <pair>\n<pair> ::= <opt> <opt> | \"q\"\n<opt> ::= \"z\" | ε\n",
+    ))
+    .iter()
+    .map(|warning| warning.to_string())
+    .collect();
+    assert_eq!(
+        warnings,
+        vec!["<pair> matches the empty string, tree-sitter only allows it for the start rule"]
+    );
+}
+
+#[test]
+fn tree_sitter_nullable_start_rule_referenced_elsewhere() {
+    let output = tree_sitter(&grammar(
+        "<list> ::= <item> | ε\n<item> ::= \"(\" <list> \")\"\n",
+    ));
+    assert!(output.contains("    list: $ => optional($.item),\n"));
+    assert!(output.contains("    item: $ => seq('(', $.list, ')'),\n"));
+}
+
+#[test]
+fn tree_sitter_ranges_outside_the_bmp() {
+    let output = tree_sitter(&grammar("<emoji> ::= \"😀\"..=\"🙏\" | \"a\"..=\"é\"\n"));
+    assert!(output.contains("choice(/[\\u{1F600}-\\u{1F64F}]/u, /[a-\\xE9]/)"));
+}
+
+#[test]
+fn rust_parser_agrees_with_the_grammar() {
+    let grammar = example("equation.bnf");
+    let inputs = [
+        "1",
+        "12+3",
+        "(1 + 2) * 3",
+        "4/ (5-6)",
+        "",
+        "1+",
+        "(1",
+        "1 ++ 2",
+        "a",
+    ];
+    let expected: Vec<String> = inputs
+        .iter()
+        .map(|input| format!("{:?}", grammar.parse(input).err()))
+        .collect();
+    assert_eq!(
+        rust_parser_errors(&grammar, &inputs, "rust-parser"),
+        expected
+    );
+    assert_eq!(expected.iter().filter(|error| *error == "None").count(), 4);
+}
+
+#[test]
+fn rust_parser_backtracks_like_the_grammar() {
+    // the left recursion is only followed once the alternative before it matched, so it overflows
+    // when nothing matches
+    let grammar = grammar("<s> ::= \"a\" | <s> \"b\"\n");
+    let inputs = ["a", "abb", "b"];
+    let expected = vec!["None", "None", "Some(StackOverflow)"];
+    for (input, error) in inputs.iter().zip(expected.iter()) {
+        assert_eq!(format!("{:?}", grammar.parse(input).err()), *error);
+    }
+    assert_eq!(
+        rust_parser_errors(&grammar, &inputs, "rust-parser-recursion"),
+        expected
+    );
+}
+
+#[test]
+fn rust_parser_build() {
+    let directory = std::env::temp_dir().join(format!("build-parser-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).expect("The directory should be created.");
+    let grammar_path = directory.join("grammar.bnf");
+    let output_path = directory.join("grammar.rs");
+
+    std::fs::write(&grammar_path, "<s> ::= \"a\" <s> | ε\n")
+        .expect("The grammar should be written.");
+    build_parser(&grammar_path, &output_path).expect("The parser should be generated.");
+    let mut expected = String::new();
+    write_rust(&grammar("<s> ::= \"a\" <s> | ε\n"), &mut expected)
+        .expect("Writing to a String should not fail.");
+    let code = std::fs::read_to_string(&output_path).expect("The parser should be readable.");
+    assert_eq!(code, expected);
+
+    std::fs::write(&grammar_path, "<s> ::= \"a\n").expect("The grammar should be written.");
+    assert!(matches!(
+        build_parser(&grammar_path, &output_path),
+        Err(RustError::InvalidGrammar)
+    ));
+    assert!(matches!(
+        build_parser(&directory.join("missing.bnf"), &output_path),
+        Err(RustError::Io(_))
+    ));
+    std::fs::remove_dir_all(&directory).expect("The directory should be removed.");
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use backus_naur_parser::bnf::grammar::Grammar;
+use backus_naur_parser::generate::enumerate::{
+    Error as EnumerateError, Options as EnumerateOptions,
+};
+use backus_naur_parser::generate::sample::{Error as SampleError, Generator};
+use common::{example, grammar};
+
+fn samples(grammar: &Grammar, seed: u64, count: usize) -> Vec<String> {
+    let mut generator = Generator::new(grammar, seed, 8).expect("The grammar should terminate.");
+    (0..count).map(|_| generator.generate()).collect()
+}
+
+#[test]
+fn samples_are_accepted() {
+    for name in [
+        "equation.bnf",
+        "harder.bnf",
+        "non_trivial.bnf",
+        "non_trivial2.bnf",
+    ] {
+        let grammar = example(name);
+        for sample in samples(&grammar, 7, 50) {
+            assert!(grammar.parse(&sample).is_ok(), "{sample:?} isn't accepted");
+        }
+    }
+}
+
+#[test]
+fn samples_depend_on_the_seed_only() {
+    let grammar = example("equation.bnf");
+    assert_eq!(samples(&grammar, 1, 20), samples(&grammar, 1, 20));
+    assert_ne!(samples(&grammar, 1, 20), samples(&grammar, 2, 20));
+}
+
+#[test]
+fn samples_of_a_grammar_recursing_forever() {
+    let grammar = grammar("<a> ::= \"x\" <b>\n<b> ::= <a> \"y\"\n");
+    let result = Generator::new(&grammar, 0, 8);
+    assert!(matches!(result, Err(SampleError::NoTerminatingDerivation(rule)) if rule == "a"));
+}
+
+#[test]
+fn enumerate_in_shortlex_order() {
+    let grammar = grammar("<s> ::= \"(\" <s> \")\" <s> | ε\n");
+    let options = EnumerateOptions {
+        max_length: 4,
+        ..EnumerateOptions::default()
+    };
+    let strings = grammar
+        .enumerate(&options)
+        .expect("The grammar should match few strings.");
+    assert_eq!(strings, vec!["", "()", "(())", "()()"]);
+}
+
+#[test]
+fn enumerate_range_samples() {
+    let grammar = grammar("<s> ::= \"a\"..=\"z\" | \"0\"..=\"9\" \"!\"\n");
+    let options = EnumerateOptions {
+        max_length: 2,
+        range_samples: Some(3),
+        ..EnumerateOptions::default()
+    };
+    let strings = grammar
+        .enumerate(&options)
+        .expect("The grammar should match few strings.");
+    assert_eq!(strings, vec!["a", "m", "z", "0!", "4!", "9!"]);
+}
+
+#[test]
+fn enumerate_too_many_strings() {
+    let grammar = grammar("<s> ::= \"a\"..=\"z\" <s> | ε\n");
+    let options = EnumerateOptions {
+        max_length: 6,
+        max_strings: 1000,
+        ..EnumerateOptions::default()
+    };
+    let result = grammar.enumerate(&options);
+    assert!(matches!(result, Err(EnumerateError::TooManyStrings(rule)) if rule == "s"));
+}
@@ -0,0 +1,307 @@
+mod common;
+
+use backus_naur_parser::bnf::debugger::{Breakpoint, Command, Debugger, Pause};
+use backus_naur_parser::bnf::grammar::{Error, Grammar};
+use backus_naur_parser::bnf::pretty::{write_pretty, Options};
+use backus_naur_parser::bnf::profile::{write_profile, write_profile_json, Counters};
+use backus_naur_parser::bnf::trace::{write_step, TraceFormat};
+use backus_naur_parser::bnf::tree::{write_tree, write_tree_json, write_tree_sexp, write_tree_xml};
+use common::{example, grammar};
+
+// the steps the debugger paused at, answering each pause with the next command
+fn pauses(
+    grammar: &Grammar,
+    input: &str,
+    breakpoints: Vec<Breakpoint>,
+    commands: &[Command],
+) -> (Result<(), Error>, Vec<String>) {
+    let mut pauses = Vec::new();
+    let mut commands = commands.iter();
+    let mut debugger = Debugger::new(breakpoints, |pause: &Pause, _: &mut Vec<Breakpoint>| {
+        let mut step = String::new();
+        write_step(&mut step, grammar, pause.event).expect("Writing to a String should not fail.");
+        if pause.breakpoint.is_some() {
+            step.push_str(" (breakpoint)");
+        }
+        pauses.push(step);
+        commands.next().copied().unwrap_or(Command::Continue)
+    });
+    let result = grammar.parse_observed(input, &mut debugger).map(|_| ());
+    (result, pauses)
+}
+
+#[test]
+fn ranges_over_non_ascii_characters() {
+    let grammar = grammar("<letter> ::= \"é\"..=\"ü\"\n");
+    assert!(grammar.parse("ö").is_ok());
+    assert!(grammar.parse("e").is_err());
+}
+
+#[test]
+fn blank_lines_between_rules() {
+    let grammar = grammar("<a> ::= <b> <c>\n\n\n<b> ::= \"b\"\n\n\n\n<c> ::= \"c\"\n");
+    assert!(grammar.parse("bc").is_ok());
+}
+
+#[test]
+fn continuation_lines_add_alternatives() {
+    let grammar =
+        grammar("<digit> ::= \"0\" | \"1\"\n          | \"2\"\n  | \"3\"\n<other> ::= \"x\"\n");
+    assert!(grammar.parse("2").is_ok());
+    assert!(grammar.parse("3").is_ok());
+    assert!(grammar.parse("x").is_err());
+}
+
+#[test]
+fn pretty_layout_is_stable() {
+    for name in [
+        "easy.bnf",
+        "equation.bnf",
+        "equation_optimized.bnf",
+        "harder.bnf",
+        "non_trivial.bnf",
+        "non_trivial2.bnf",
+        "really_easy.bnf",
+    ] {
+        let original = example(name);
+        for width in [20, 60, 100] {
+            let options = Options { width };
+            let mut once = String::new();
+            write_pretty(&original, &options, &mut once)
+                .expect("Writing to a String should not fail.");
+            let mut twice = String::new();
+            write_pretty(&grammar(&once), &options, &mut twice)
+                .expect("Writing to a String should not fail.");
+            assert_eq!(once, twice, "{name} at width {width}");
+            // the layout doesn't change the grammar
+            assert_eq!(grammar(&once).maps, original.maps, "{name}");
+        }
+    }
+}
+
+#[test]
+fn trace_written_step_by_step() {
+    let grammar = grammar("<s> ::= <a> \"b\" | <a> \"c\"\n<a> ::= \"a\"\n");
+    let (result, written) = grammar.parse_traced("ac", Vec::new(), TraceFormat::Text);
+    assert!(result.is_ok());
+    let text = String::from_utf8(written.expect("Writing to a Vec should not fail."))
+        .expect("The trace should be UTF-8.");
+    assert_eq!(
+        text,
+        "enter <s> at 0
+try <s> alternative 1 at 0
+  enter <a> at 0
+  try <a> alternative 1 at 0
+  match \"a\" at 0..1
+  exit <a> alternative 1 at 0..1
+mismatch \"b\" at 1
+  backtrack to <a> at 0
+  fail <a> at 0
+backtrack to <s> at 0
+try <s> alternative 2 at 0
+  enter <a> at 0
+  try <a> alternative 1 at 0
+  match \"a\" at 0..1
+  exit <a> alternative 1 at 0..1
+match \"c\" at 1..2
+exit <s> alternative 2 at 0..2
+"
+    );
+
+    let (_, written) = grammar.parse_traced("ac", Vec::new(), TraceFormat::Json);
+    let json = String::from_utf8(written.expect("Writing to a Vec should not fail."))
+        .expect("The trace should be UTF-8.");
+    assert_eq!(json.lines().count(), text.lines().count());
+    assert_eq!(
+        json.lines().nth(6),
+        Some("{\"event\":\"mismatch\",\"depth\":1,\"element\":\"\\\"b\\\"\",\"offset\":1}")
+    );
+    // the alternatives are counted from 1, like in the text
+    assert_eq!(
+        json.lines().nth(10),
+        Some("{\"event\":\"attempt\",\"depth\":1,\"rule\":\"s\",\"alternative\":2,\"offset\":0}")
+    );
+}
+
+#[test]
+fn debugger_pauses() {
+    let grammar = grammar("<s> ::= <a> \"b\" | <a> \"c\"\n<a> ::= \"a\"\n");
+    let a = Breakpoint::Rule(grammar.label_index("a").expect("<a> should exist."));
+
+    let (result, steps) = pauses(&grammar, "ac", vec![a], &[]);
+    assert!(result.is_ok());
+    assert_eq!(
+        steps,
+        vec!["enter <a> at 0 (breakpoint)", "enter <a> at 0 (breakpoint)"]
+    );
+
+    let (result, steps) = pauses(
+        &grammar,
+        "ac",
+        Vec::new(),
+        &[
+            Command::StepInto,
+            Command::StepInto,
+            Command::StepOver,
+            Command::StepOut,
+        ],
+    );
+    assert!(result.is_ok());
+    assert_eq!(
+        steps,
+        vec![
+            "enter <s> at 0",
+            "try <s> alternative 1 at 0",
+            "enter <a> at 0",
+            // stepping over <a> skips its steps, out of <s> goes to the end of the parse
+            "mismatch \"b\" at 1",
+        ]
+    );
+
+    let (result, steps) = pauses(
+        &grammar,
+        "ac",
+        vec![Breakpoint::Offset(1)],
+        &[Command::Quit],
+    );
+    assert!(matches!(result, Err(Error::Stopped)));
+    assert_eq!(steps, vec!["match \"a\" at 0..1 (breakpoint)"]);
+}
+
+#[test]
+fn profile_counters() {
+    let grammar = grammar("<s> ::= <a> \"b\" | <a> \"c\"\n<a> ::= \"a\"\n");
+    let (result, profile) = grammar.parse_profiled("ac");
+    assert!(result.is_ok());
+    let s = grammar.label_index("s").expect("<s> should exist.");
+    let a = grammar.label_index("a").expect("<a> should exist.");
+    let counters = |counters: &Counters| {
+        (
+            counters.entries,
+            counters.matches,
+            counters.comparisons,
+            counters.backtracks,
+        )
+    };
+    assert_eq!(counters(&profile.rules[&s]), (1, 1, 2, 1));
+    // the first alternative fails on "b", the second matches "c"
+    assert_eq!(counters(&profile.alternatives[&(s, 0)]), (1, 0, 1, 1));
+    assert_eq!(counters(&profile.alternatives[&(s, 1)]), (1, 1, 1, 0));
+    // <a> is parsed again after the backtrack, having no other alternative to give
+    assert_eq!(counters(&profile.rules[&a]), (2, 2, 2, 1));
+    assert_eq!(
+        profile.rules[&s].time + profile.rules[&a].time,
+        profile.total
+    );
+
+    // the times change from a run to the other, and so does the order of the rules
+    let mut text = String::new();
+    write_profile(&mut text, &grammar, &profile).expect("Writing to a String should not fail.");
+    let rows: Vec<Vec<&str>> = text
+        .lines()
+        .map(|line| line.split_whitespace().collect())
+        .collect();
+    assert_eq!(
+        rows[0],
+        vec![
+            "rule",
+            "entries",
+            "matches",
+            "comparisons",
+            "backtracks",
+            "time",
+            "%"
+        ]
+    );
+    assert_eq!(rows.len(), 7);
+    let s_row = rows
+        .iter()
+        .position(|row| row[0] == "<s>")
+        .expect("<s> should be in the table.");
+    assert_eq!(rows[s_row][1..5], ["1", "1", "2", "1"]);
+    assert_eq!(
+        rows[s_row + 1][..6],
+        ["alternative", "1", "1", "0", "1", "1"]
+    );
+    assert_eq!(
+        rows[s_row + 2][..6],
+        ["alternative", "2", "1", "1", "1", "0"]
+    );
+    assert!(rows[6][0] == "total");
+
+    let mut json = String::new();
+    write_profile_json(&mut json, &grammar, &profile)
+        .expect("Writing to a String should not fail.");
+    assert!(json.contains(
+        "{\"rule\":\"s\",\"entries\":1,\"matches\":1,\"comparisons\":2,\"backtracks\":1,"
+    ));
+    assert!(json.contains(
+        "\"alternatives\":[{\"alternative\":1,\"entries\":1,\"matches\":0,\"comparisons\":1,\"backtracks\":1,"
+    ));
+}
+
+#[test]
+fn tree_xml_with_control_characters() {
+    let grammar = grammar("<s> ::= <c> <c> \"<\"\n<c> ::= \"\u{0}\"..=\"\u{1f}\"\n");
+    let input = "\u{0}\n<";
+    let chars: Vec<char> = input.chars().collect();
+    let choices = grammar.parse(input).expect("The input should be accepted.");
+    let mut xml = String::new();
+    write_tree_xml(&mut xml, &grammar, &grammar.tree(&choices), &chars)
+        .expect("Writing to a String should not fail.");
+    assert_eq!(
+        xml,
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<rule name=\"s\" alternative=\"1\" start=\"0\" end=\"3\">
+  <rule name=\"c\" alternative=\"1\" start=\"0\" end=\"1\">
+    <terminal start=\"0\" end=\"1\"><char code=\"0\"/></terminal>
+  </rule>
+  <rule name=\"c\" alternative=\"1\" start=\"1\" end=\"2\">
+    <terminal start=\"1\" end=\"2\">&#xA;</terminal>
+  </rule>
+  <terminal start=\"2\" end=\"3\">&lt;</terminal>
+</rule>
+"
+    );
+}
+
+#[test]
+fn tree_json_and_sexp() {
+    let grammar = grammar(
+        r##"<s> ::= <a> "b" | <a> #"""#
+<a> ::= "a"..="z"
+"##,
+    );
+    let input = "q\"";
+    let chars: Vec<char> = input.chars().collect();
+    let tree = grammar
+        .parse_tree(input)
+        .expect("The input should be accepted.");
+    let mut json = String::new();
+    write_tree_json(&mut json, &grammar, &tree, &chars)
+        .expect("Writing to a String should not fail.");
+    let mut sexp = String::new();
+    write_tree_sexp(&mut sexp, &grammar, &tree, &chars)
+        .expect("Writing to a String should not fail.");
+    let mut text = String::new();
+    write_tree(&mut text, &grammar, &tree, &chars).expect("Writing to a String should not fail.");
+    assert_eq!(
+        json,
+        r#"{"type":"rule","rule":"s","alternative":2,"start":0,"end":2,"children":[{"type":"rule","rule":"a","alternative":1,"start":0,"end":1,"children":[{"type":"terminal","start":0,"end":1,"text":"q"}]},{"type":"terminal","start":1,"end":2,"text":"\""}]}
+"#
+    );
+    assert_eq!(
+        sexp,
+        r#"(rule "s" (alternative 2) (span 0 2) (rule "a" (alternative 1) (span 0 1) (terminal (span 0 1) "q")) (terminal (span 1 2) "\""))
+"#
+    );
+    // the text tree counts the alternatives from 1 too
+    assert_eq!(
+        text,
+        r#"<s> alternative 2, 0..2 "q\""
+  <a> alternative 1, 0..1 "q"
+    "q"
+  "\""
+"#
+    );
+}
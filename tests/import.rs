@@ -0,0 +1,119 @@
+mod common;
+
+use backus_naur_parser::export::abnf::write_abnf;
+use backus_naur_parser::export::w3c_ebnf::write_w3c_ebnf;
+use backus_naur_parser::import::abnf::{parse_abnf, Error};
+use backus_naur_parser::import::w3c_ebnf::{parse_w3c_ebnf, Error as W3cError};
+use common::{assert_equivalent, example};
+
+#[test]
+fn abnf_round_trip() {
+    for name in [
+        "equation.bnf",
+        "harder.bnf",
+        "non_trivial.bnf",
+        "non_trivial2.bnf",
+    ] {
+        let original = example(name);
+        let mut abnf = String::new();
+        write_abnf(&original, &mut abnf).expect("Writing to a String should not fail.");
+        let imported = parse_abnf(&abnf).expect("The written ABNF should be valid.");
+        assert_equivalent(&original, &imported);
+    }
+}
+
+#[test]
+fn abnf_repetitions_and_incremental_alternatives() {
+    let grammar = parse_abnf(
+        "date = 4DIGIT \"-\" 2DIGIT [\"-\" 1*2DIGIT]\ndate =/ \"today\" ; case insensitive\n",
+    )
+    .expect("The grammar should be valid.");
+    for input in ["2024-01", "2024-01-5", "2024-01-31", "today", "ToDay"] {
+        assert!(grammar.parse(input).is_ok(), "{input:?} should be accepted");
+    }
+    for input in ["224-01", "2024-01-", "2024-01-123", "tomorrow"] {
+        assert!(
+            grammar.parse(input).is_err(),
+            "{input:?} should be rejected"
+        );
+    }
+}
+
+#[test]
+fn abnf_errors() {
+    assert!(matches!(parse_abnf("a = b\n"), Err(Error::UndefinedRule(rule)) if rule == "b"));
+    assert!(matches!(
+        parse_abnf("a = \"x\"\nA = \"y\"\n"),
+        Err(Error::DuplicateRule(rule)) if rule == "A"
+    ));
+    assert!(matches!(
+        parse_abnf("a = \"x\"\nb =/ \"y\"\n"),
+        Err(Error::IncrementOfUndefinedRule(rule)) if rule == "b"
+    ));
+    assert!(matches!(parse_abnf("; nothing\n"), Err(Error::NoRules)));
+}
+
+#[test]
+fn abnf_angle_brackets_in_quoted_strings() {
+    let grammar = parse_abnf("tag = \"<\" ALPHA \">\" ; not a <prose> value\n")
+        .expect("The grammar should be valid.");
+    assert!(grammar.parse("<b>").is_ok());
+    assert!(grammar.parse("b").is_err());
+}
+
+#[test]
+fn abnf_prose_values() {
+    let result = parse_abnf("greeting = \"hi\"\nname = \"<\"\n  / <any name>\n");
+    assert!(matches!(result, Err(Error::Prose { line: 2 })));
+    let result = parse_abnf("name = \"<\" ?\n");
+    assert!(matches!(result, Err(Error::Syntax { line: 1 })));
+}
+
+#[test]
+fn w3c_ebnf_round_trip() {
+    for name in [
+        "equation.bnf",
+        "harder.bnf",
+        "non_trivial.bnf",
+        "non_trivial2.bnf",
+    ] {
+        let original = example(name);
+        let mut w3c = String::new();
+        write_w3c_ebnf(&original, &mut w3c).expect("Writing to a String should not fail.");
+        let imported = parse_w3c_ebnf(&w3c).expect("The written EBNF should be valid.");
+        assert_equivalent(&original, &imported);
+    }
+}
+
+#[test]
+fn w3c_ebnf_of_the_xml_specification() {
+    let grammar = parse_w3c_ebnf(
+        "/* from the XML specification */
+[5]  Name          ::= NameStartChar (NameChar)*
+[4]  NameStartChar ::= \":\" | [A-Z] | \"_\" | [a-z] | [#xC0-#xD6]
+[4a] NameChar      ::= NameStartChar | \"-\" | \".\" | [0-9]
+[10] AttValue      ::= '\"' ([^<&\"])* '\"' [ wfc: No < in Attribute Values ]
+",
+    )
+    .expect("The grammar should be valid.");
+    for input in ["a", "_x-1.b", "Ö9"] {
+        assert!(grammar.parse(input).is_ok(), "{input:?} should be accepted");
+    }
+    for input in ["", "-a", "a b"] {
+        assert!(
+            grammar.parse(input).is_err(),
+            "{input:?} should be rejected"
+        );
+    }
+}
+
+#[test]
+fn w3c_ebnf_unsupported_exceptions() {
+    let result =
+        parse_w3c_ebnf("Name ::= [a-z]+\nPITarget ::= Name - 'xml'\nChar ::= [a-z] - 'q'\n");
+    let Err(W3cError::Unsupported(unsupported)) = result else {
+        panic!("The exception on a name should be unsupported.");
+    };
+    let rules: Vec<&str> = unsupported.iter().map(|item| item.rule.as_str()).collect();
+    assert_eq!(rules, vec!["PITarget"]);
+}
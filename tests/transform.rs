@@ -0,0 +1,167 @@
+mod common;
+
+use backus_naur_parser::bnf::element::Element;
+use backus_naur_parser::transform::left_recursion::Error as LeftRecursionError;
+use backus_naur_parser::transform::normal_form::Error as NormalFormError;
+use common::{assert_equivalent, example, grammar};
+
+fn is_terminal(element: &Element) -> bool {
+    match element {
+        Element::Literal(literal) => literal.chars().count() == 1,
+        Element::Range { .. } => true,
+        Element::Empty | Element::Reference(_) => false,
+    }
+}
+
+#[test]
+fn left_factor_keeps_the_language() {
+    for name in [
+        "equation.bnf",
+        "harder.bnf",
+        "non_trivial.bnf",
+        "non_trivial2.bnf",
+    ] {
+        let original = example(name);
+        assert_equivalent(&original, &original.left_factor());
+    }
+}
+
+#[test]
+fn left_factor_shares_prefixes() {
+    let factored = grammar("<s> ::= \"a\" \"b\" | \"a\" \"c\"\n").left_factor();
+    let rule = &factored.maps[&factored.entrypoint()];
+    assert_eq!(rule.sequences.len(), 1);
+    assert_equivalent(&factored, &grammar("<s> ::= \"ab\" | \"ac\"\n"));
+}
+
+#[test]
+fn eliminate_left_recursion_keeps_the_language() {
+    // the left recursive grammar overflows the stack, so it is compared to a right recursive one
+    let left = grammar("<e> ::= <e> \"+\" <t> | <e> \"-\" <t> | <t>\n<t> ::= \"a\" | \"b\"\n");
+    let right = grammar("<e> ::= <t> \"+\" <e> | <t> \"-\" <e> | <t>\n<t> ::= \"a\" | \"b\"\n");
+    let eliminated = left
+        .eliminate_left_recursion()
+        .expect("The left recursion should be eliminated.");
+    assert_equivalent(&eliminated, &right);
+}
+
+#[test]
+fn eliminate_indirect_left_recursion() {
+    let left = grammar("<a> ::= <b> \"x\" | \"y\"\n<b> ::= <a> \"z\" | \"w\"\n");
+    let eliminated = left
+        .eliminate_left_recursion()
+        .expect("The left recursion should be eliminated.");
+    // <a> is y or wx, followed by any number of zx
+    let expected = grammar(
+        "<a> ::= <start> <rest>\n<start> ::= \"y\" | \"wx\"\n<rest> ::= \"zx\" <rest> | ε\n",
+    );
+    assert_equivalent(&eliminated, &expected);
+}
+
+#[test]
+fn eliminate_left_recursion_without_base_case() {
+    let result = grammar("<a> ::= <a> \"x\"\n").eliminate_left_recursion();
+    assert!(matches!(result, Err(LeftRecursionError::NoBaseCase(rule)) if rule == "a"));
+}
+
+#[test]
+fn cnf_keeps_the_language() {
+    for name in [
+        "equation.bnf",
+        "harder.bnf",
+        "non_trivial.bnf",
+        "non_trivial2.bnf",
+    ] {
+        let original = example(name);
+        let form = original.to_cnf().expect("The language shouldn't be empty.");
+        assert_equivalent(&original, &form.grammar);
+    }
+}
+
+#[test]
+fn cnf_rules_have_two_references_or_a_terminal() {
+    let form = grammar("<s> ::= <s> \"ab\" <s> | \"(\" <s> \")\" | ε\n")
+        .to_cnf()
+        .expect("The language shouldn't be empty.");
+    let entrypoint = form.grammar.entrypoint();
+    for (label_index, alternation) in form.grammar.maps.iter() {
+        for sequence in alternation.sequences.iter() {
+            let elements = &sequence.elements;
+            let valid = match elements.as_slice() {
+                [Element::Reference(b), Element::Reference(c)] => {
+                    *b != entrypoint && *c != entrypoint
+                }
+                [terminal] if is_terminal(terminal) => true,
+                [Element::Empty] => *label_index == entrypoint,
+                _ => false,
+            };
+            assert!(valid, "{elements:?} isn't in Chomsky normal form");
+        }
+    }
+}
+
+#[test]
+fn gnf_keeps_the_language() {
+    let original =
+        grammar("<e> ::= <t> \"+\" <e> | <t>\n<t> ::= \"(\" <e> \")\" | \"0\"..=\"9\" | ε\n");
+    let form = original.to_gnf().expect("The language shouldn't be empty.");
+    assert_equivalent(&original, &form.grammar);
+    let entrypoint = form.grammar.entrypoint();
+    for (label_index, alternation) in form.grammar.maps.iter() {
+        for sequence in alternation.sequences.iter() {
+            let elements = &sequence.elements;
+            let valid = match elements.split_first() {
+                Some((Element::Empty, [])) => *label_index == entrypoint,
+                Some((first, rest)) => {
+                    is_terminal(first)
+                        && rest
+                            .iter()
+                            .all(|element| matches!(element, Element::Reference(_)))
+                }
+                None => false,
+            };
+            assert!(valid, "{elements:?} isn't in Greibach normal form");
+        }
+    }
+}
+
+#[test]
+fn normal_form_of_an_empty_language() {
+    let result = grammar("<a> ::= <a> \"x\"\n").to_cnf();
+    assert!(matches!(result, Err(NormalFormError::EmptyLanguage)));
+}
+
+#[test]
+fn simplify_keeps_the_language() {
+    for name in [
+        "equation.bnf",
+        "harder.bnf",
+        "non_trivial.bnf",
+        "non_trivial2.bnf",
+    ] {
+        let original = example(name);
+        let (simplified, _) = original.simplify();
+        assert_equivalent(&original, &simplified);
+        // the changes are made until there are none left
+        assert!(simplified.simplify().1.is_empty());
+    }
+}
+
+#[test]
+fn simplify_reports_its_changes() {
+    let original = grammar(
+        "<s> ::= <a> ε <b> | <a> <b> | <c>\n<a> ::= \"x\" | \"y\"\n<b> ::= \"x\" | \"y\"\n<c> ::= \"z\"\n",
+    );
+    let (simplified, changes) = original.simplify();
+    let changes: Vec<String> = changes.iter().map(|change| change.to_string()).collect();
+    assert_eq!(
+        changes,
+        vec![
+            "removed ε from alternative 1 of <s>",
+            "removed duplicate alternative 2 of <s>",
+            "merged <b> into <a>",
+            "inlined <c>",
+        ]
+    );
+    assert_equivalent(&original, &simplified);
+}